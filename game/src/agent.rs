@@ -0,0 +1,274 @@
+use rand::{Rng, SeedableRng};
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand_chacha::ChaCha8Rng;
+use skat_solver::bidding::{BiddingStrategy, Position, SolverStrategy};
+use skat_solver::contract::{Contract, ContractAdvisor, CONTRACTS};
+use skat_solver::discard::best_discard_sampled;
+use skat_solver::pimc::{Pimc, PlayerView};
+use skat_solver::scoring::Announcement;
+use skat_solver::solver::bitboard::{BitCard, BitCards};
+use skat_solver::solver::bitstates::{BitGlobal, BitLocal};
+use skat_solver::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+use skat_solver::solver::{Player, Variant};
+use crate::engine::{Declaration, Trick};
+
+///
+/// Decisions of one seat in a game. The engine checks every decision and
+/// asks for the declaration after the skat was picked up and put down.
+pub trait PlayerAgent: BiddingStrategy {
+    ///true to pick up the skat, false to play a hand game
+    fn pick_up_skat(&mut self, hand: BitCards, position: Position, bid: u16) -> bool;
+    ///two of the twelve cards which go back into the skat
+    fn discard(&mut self, cards: BitCards, position: Position, bid: u16) -> BitCards;
+    fn declare(&mut self, hand: BitCards, position: Position, hand_game: bool, bid: u16) -> Declaration;
+    ///one of the legal cards, which are never empty
+    fn play_card(&mut self, view: &PlayerView, legal_cards: BitCards) -> BitCard;
+    ///Called for every agent after the third card of a trick
+    fn trick_finished(&mut self, _view: &PlayerView, _trick: &Trick) {}
+}
+
+///Suit games, grand and null without announcements
+const VARIANTS: [Variant; 6] = [Variant::Grand, Variant::Clubs, Variant::Spades, Variant::Hearts, Variant::Diamonds, Variant::Null];
+
+///Takes every decision randomly, the cards are always legal
+pub struct RandomAgent {
+    rng: ChaCha8Rng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> RandomAgent {
+        RandomAgent {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl BiddingStrategy for RandomAgent {
+    fn bid(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+        self.rng.gen_bool(0.5)
+    }
+
+    fn hold(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+        self.rng.gen_bool(0.5)
+    }
+}
+
+impl PlayerAgent for RandomAgent {
+    fn pick_up_skat(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+        self.rng.gen_bool(0.5)
+    }
+
+    fn discard(&mut self, cards: BitCards, _position: Position, _bid: u16) -> BitCards {
+        cards.choose_multiple(&mut self.rng, 2).into_iter().fold(BitCards(0), |cards, card| cards | card)
+    }
+
+    fn declare(&mut self, _hand: BitCards, _position: Position, hand_game: bool, _bid: u16) -> Declaration {
+        let variant = match *VARIANTS.choose(&mut self.rng).unwrap() {
+            Variant::Null if hand_game => Variant::NullHand,
+            variant => variant,
+        };
+        Declaration {
+            variant,
+            announcement: Announcement { hand: hand_game, ..Default::default() },
+        }
+    }
+
+    fn play_card(&mut self, _view: &PlayerView, legal_cards: BitCards) -> BitCard {
+        legal_cards.choose(&mut self.rng).unwrap()
+    }
+}
+
+///
+/// Bids with the solver strategy on hand contracts and plays with perfect information monte carlo.
+/// Solving every discard is expensive, so the skat is put down after solving a single sample.
+pub struct SolverAgent {
+    samples: usize,
+    seed: u64,
+    bidding: SolverStrategy,
+    pimc: Pimc,
+    hand_contracts: Vec<Contract>,
+    //contract chosen before the skat was picked up
+    contract: Option<Contract>,
+}
+
+impl SolverAgent {
+    pub fn new(samples: usize, seed: u64) -> SolverAgent {
+        let hand_contracts: Vec<Contract> = CONTRACTS.iter().filter(|contract| contract.hand).copied().collect();
+        SolverAgent {
+            samples,
+            seed,
+            bidding: SolverStrategy::new(samples, seed).with_contracts(&hand_contracts),
+            pimc: Pimc::new(samples, seed),
+            hand_contracts,
+            contract: None,
+        }
+    }
+
+    ///The variant of the chosen contract, null games without hand if the skat was picked up
+    fn get_variant(&self, hand_game: bool) -> Variant {
+        match self.contract.map_or(Variant::Grand, |contract| contract.variant) {
+            Variant::NullHand if !hand_game => Variant::Null,
+            Variant::NullOuvertHand if !hand_game => Variant::NullOuvert,
+            variant => variant,
+        }
+    }
+}
+
+impl BiddingStrategy for SolverAgent {
+    fn bid(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        self.bidding.bid(hand, position, bid)
+    }
+
+    fn hold(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        self.bidding.hold(hand, position, bid)
+    }
+}
+
+impl PlayerAgent for SolverAgent {
+    fn pick_up_skat(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        //the seats are relative to Vorhand as player one
+        let advices = ContractAdvisor::new(self.samples, self.seed)
+            .with_bid(bid)
+            .with_contracts(&self.hand_contracts)
+            .advise(hand, None, position.get_player(Player::One), Player::One);
+        self.contract = Some(advices[0].contract);
+        //a hand game is only played if it is won in every sample
+        advices[0].win_probability < 1.0
+    }
+
+    fn discard(&mut self, cards: BitCards, position: Position, _bid: u16) -> BitCards {
+        let variant = self.get_variant(false);
+        let discards = best_discard_sampled(cards, BitCards(!cards.0), position.get_player(Player::One), Player::One, variant, 1, self.seed);
+        discards[0].skat
+    }
+
+    fn declare(&mut self, _hand: BitCards, _position: Position, hand_game: bool, _bid: u16) -> Declaration {
+        let variant = self.get_variant(hand_game);
+        self.contract = None;
+        Declaration {
+            variant,
+            announcement: Announcement { hand: hand_game, ..Default::default() },
+        }
+    }
+
+    fn play_card(&mut self, view: &PlayerView, legal_cards: BitCards) -> BitCard {
+        match self.pimc.best_move(view) {
+            Some(card) if card.0 & legal_cards.0 != 0 => card,
+            _ => legal_cards.into_iter().next().unwrap(),
+        }
+    }
+}
+
+///
+/// Sees every hand and plays the card of the double dummy solver. It only plays cards,
+/// it never bids and so never has to handle the skat.
+pub struct DoubleDummyAgent {
+    //the hands at the start of the card play
+    hands: [BitCards; 3],
+    //created at the first card, the table is kept for the whole game
+    solver: Option<MoreEnhancedSolver>,
+}
+
+impl DoubleDummyAgent {
+    pub fn new(hands: [BitCards; 3]) -> DoubleDummyAgent {
+        DoubleDummyAgent {
+            hands,
+            solver: None,
+        }
+    }
+}
+
+impl BiddingStrategy for DoubleDummyAgent {
+    fn bid(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+        false
+    }
+
+    fn hold(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+        false
+    }
+}
+
+impl PlayerAgent for DoubleDummyAgent {
+    fn pick_up_skat(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+        false
+    }
+
+    fn discard(&mut self, cards: BitCards, _position: Position, _bid: u16) -> BitCards {
+        cards.take(2).fold(BitCards(0), |cards, card| cards | card)
+    }
+
+    fn declare(&mut self, _hand: BitCards, _position: Position, hand_game: bool, _bid: u16) -> Declaration {
+        Declaration {
+            variant: Variant::Grand,
+            announcement: Announcement { hand: hand_game, ..Default::default() },
+        }
+    }
+
+    fn play_card(&mut self, view: &PlayerView, legal_cards: BitCards) -> BitCard {
+        let hands = self.hands;
+        let solver = self.solver.get_or_insert_with(|| {
+            MoreEnhancedSolver::new(BitGlobal::new(hands[0], hands[1], hands[2], view.declarer, view.variant))
+        });
+        let played = view.played_cards.iter().fold(BitCards(0), |cards, card| cards | *card);
+        let trick = &view.played_cards[view.played_cards.len() - view.played_cards.len() % 3..];
+        let remaining = (hands[0] | hands[1] | hands[2]).0 & !played.0;
+        let best_move = BitLocal::with_trick(remaining, view.player, trick, &solver.global_state).ok()
+            .and_then(|local_state| solver.best_move(local_state));
+        match best_move {
+            Some(card) if card.0 & legal_cards.0 != 0 => card,
+            _ => legal_cards.into_iter().next().unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use skat_solver::dataset::read_dataset;
+    use skat_solver::pimc::PlayerView;
+    use skat_solver::solver::bitboard::BitCards;
+    use skat_solver::solver::{calculate_next_moves, Player};
+    use skat_solver::solver::bitstates::{BitGlobal, BitLocal};
+    use skat_solver::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use crate::agent::{DoubleDummyAgent, PlayerAgent, SolverAgent};
+
+    #[test]
+    fn solver_agent_cards() {
+        for position in read_dataset("../skat-solver/data/six_cards.txt").unwrap().into_iter().take(5) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let hand = [p1, p2, p3][current_player as usize];
+            let view = PlayerView::new(current_player, Player::One, current_player, variant, hand)
+                .with_hidden(BitCards((p1 | p2 | p3).0 & !hand.0));
+            let legal_cards = calculate_next_moves(hand, None);
+            let card = SolverAgent::new(3, 1).play_card(&view, legal_cards);
+            assert_ne!(card.0 & legal_cards.0, 0);
+        }
+    }
+
+    #[test]
+    fn double_dummy_agent_cards() {
+        for position in read_dataset("../skat-solver/data/six_cards.txt").unwrap().into_iter().take(10) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, Player::One, variant));
+            let variation = solver.principal_variation(BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state));
+            //the agent follows the principal variation into the second trick and keeps its value
+            let mut agent = DoubleDummyAgent::new([p1, p2, p3]);
+            let mut views = [Player::One, Player::Two, Player::Three].map(|player| PlayerView::new(player, Player::One, current_player, variant, [p1, p2, p3][player as usize]));
+            for card in &variation[..4] {
+                for view in &mut views {
+                    view.play(*card).unwrap();
+                }
+            }
+            let player = views[0].get_current_player();
+            let played = variation[..4].iter().fold(BitCards(0), |cards, card| cards | *card);
+            let state = BitLocal::with_trick((p1 | p2 | p3).0 & !played.0, player, &variation[3..4], &solver.global_state).unwrap();
+            let scores = solver.analyze_moves(state);
+            let card = agent.play_card(&views[player as usize], scores.iter().fold(BitCards(0), |cards, (card, _)| cards | *card));
+            let score = scores.iter().find(|(next, _)| *next == card).unwrap().1;
+            assert_eq!(score, scores[0].1);
+        }
+    }
+}
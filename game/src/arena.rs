@@ -0,0 +1,313 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use skat_solver::scoring::Announcement;
+use skat_solver::solver::bitboard::BitCards;
+use skat_solver::solver::{Player, Variant};
+use crate::agent::PlayerAgent;
+use crate::engine::{deal_cards, Declaration, GameError, play_declared_game};
+
+///Creates the agent of a strategy for one game, the agent may look at the dealt hands
+pub type AgentFactory = fn(hands: [BitCards; 3], seed: u64) -> Box<dyn PlayerAgent>;
+
+pub struct Strategy {
+    pub name: String,
+    pub create: AgentFactory,
+}
+
+///Running sums of one integer value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Statistic {
+    pub count: u64,
+    pub sum: i64,
+    pub sum_squares: i64,
+}
+
+impl Statistic {
+    pub fn add(&mut self, value: i64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_squares += value * value;
+    }
+
+    pub fn merge(&mut self, other: &Statistic) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    ///Half width of the 95% confidence interval of the mean, normal approximation
+    pub fn confidence_interval(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let count = self.count as f64;
+        let variance = (self.sum_squares as f64 - self.sum as f64 * self.sum as f64 / count) / (count - 1.0);
+        1.96 * (variance.max(0.0) / count).sqrt()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoleStatistics {
+    ///one for every won game, the defenders win if the declarer loses
+    pub wins: Statistic,
+    ///card points of the own side, the defenders count theirs together
+    pub points: Statistic,
+    ///score of the declarer, negated for the defenders
+    pub game_value: Statistic,
+}
+
+impl RoleStatistics {
+    fn merge(&mut self, other: &RoleStatistics) {
+        self.wins.merge(&other.wins);
+        self.points.merge(&other.points);
+        self.game_value.merge(&other.game_value);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategyReport {
+    pub name: String,
+    pub declarer: RoleStatistics,
+    pub defender: RoleStatistics,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArenaReport {
+    pub deals: usize,
+    pub strategies: Vec<StrategyReport>,
+}
+
+impl Display for ArenaReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} deals", self.deals)?;
+        writeln!(f, "{:<16}{:<10}{:>7}  {:<18}{:<18}game value", "strategy", "role", "games", "win rate", "card points")?;
+        for strategy in &self.strategies {
+            for (role, statistics) in [("declarer", &strategy.declarer), ("defender", &strategy.defender)] {
+                let format = |statistic: &Statistic, precision: usize| format!("{:.precision$} ± {:.precision$}", statistic.mean(), statistic.confidence_interval());
+                writeln!(f, "{:<16}{:<10}{:>7}  {:<18}{:<18}{}", strategy.name, role, statistics.wins.count,
+                         format(&statistics.wins, 3), format(&statistics.points, 1), format(&statistics.game_value, 1))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// Plays seeded deals between strategies. The first hand of every deal is played by the declarer of a hand game,
+/// every strategy plays it against every other strategy with the declarer in every seat, Vorhand is player one.
+pub struct Arena {
+    strategies: Vec<Strategy>,
+    deals: usize,
+    seed: u64,
+    threads: usize,
+}
+
+impl Arena {
+    pub fn new(strategies: Vec<Strategy>, deals: usize, seed: u64) -> Arena {
+        Arena {
+            strategies,
+            deals,
+            seed,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Arena {
+        self.threads = threads.max(1);
+        self
+    }
+
+    ///The report does not depend on the amount of threads
+    pub fn run(&self) -> Result<ArenaReport, GameError> {
+        let next_deal = AtomicUsize::new(0);
+        let results: Vec<Result<Vec<(RoleStatistics, RoleStatistics)>, GameError>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..self.threads).map(|_| scope.spawn(|| {
+                let mut statistics = vec![(RoleStatistics::default(), RoleStatistics::default()); self.strategies.len()];
+                loop {
+                    let number = next_deal.fetch_add(1, Ordering::Relaxed);
+                    if number >= self.deals {
+                        return Ok(statistics);
+                    }
+                    self.play_deal(number, &mut statistics)?;
+                }
+            })).collect();
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+        });
+        let mut strategies: Vec<StrategyReport> = self.strategies.iter()
+            .map(|strategy| StrategyReport {
+                name: strategy.name.clone(),
+                declarer: RoleStatistics::default(),
+                defender: RoleStatistics::default(),
+            })
+            .collect();
+        for result in results {
+            for (report, (declarer, defender)) in strategies.iter_mut().zip(result?) {
+                report.declarer.merge(&declarer);
+                report.defender.merge(&defender);
+            }
+        }
+        Ok(ArenaReport {
+            deals: self.deals,
+            strategies,
+        })
+    }
+
+    fn play_deal(&self, number: usize, statistics: &mut [(RoleStatistics, RoleStatistics)]) -> Result<(), GameError> {
+        let (hands, skat) = get_deal(self.seed, number);
+        let declaration = get_declaration(hands[0]);
+        for (declarer, defender) in get_lineups(self.strategies.len()) {
+            for seat in [Player::One, Player::Two, Player::Three] {
+                let seat_hands = get_seat_hands(hands, seat);
+                let seed = self.seed.wrapping_add(9 * number as u64 + 3 * seat as u64);
+                //the declarer's strategy sits in the declarer's seat, the defenders follow in playing order
+                let mut agents = [Player::One, Player::Two, Player::Three].map(|player| {
+                    let offset = (player as u64 + 3 - seat as u64) % 3;
+                    let strategy = if player == seat { declarer } else { defender };
+                    (self.strategies[strategy].create)(seat_hands, seed.wrapping_add(offset))
+                });
+                let [first, second, third] = &mut agents;
+                let game = play_declared_game(seat_hands, skat, Player::One, seat, declaration, 0, &mut [first.as_mut(), second.as_mut(), third.as_mut()])?;
+                let points = game.outcome.declarer_points as i64;
+                let score = game.result.score as i64;
+                let declarer_statistics = &mut statistics[declarer].0;
+                declarer_statistics.wins.add(game.result.won as i64);
+                declarer_statistics.points.add(points);
+                declarer_statistics.game_value.add(score);
+                let defender_statistics = &mut statistics[defender].1;
+                defender_statistics.wins.add(!game.result.won as i64);
+                defender_statistics.points.add(120 - points);
+                defender_statistics.game_value.add(-score);
+            }
+        }
+        Ok(())
+    }
+}
+
+///The hands of the players if the declarer gets the first hand of the deal and the others follow in playing order
+pub fn get_seat_hands(hands: [BitCards; 3], declarer: Player) -> [BitCards; 3] {
+    [Player::One, Player::Two, Player::Three].map(|player| hands[(player as usize + 3 - declarer as usize) % 3])
+}
+
+///Every deal has its own stream of the seeded generator
+pub fn get_deal(seed: u64, number: usize) -> ([BitCards; 3], BitCards) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(number as u64);
+    deal_cards(&mut rng)
+}
+
+///The suit game with the most trumps in the hand, on a tie the higher suit
+pub fn get_declaration(hand: BitCards) -> Declaration {
+    let variant = [Variant::Diamonds, Variant::Hearts, Variant::Spades, Variant::Clubs].into_iter()
+        .max_by_key(|variant| (hand.0 & variant.get_binary_mask()).count_ones())
+        .unwrap();
+    Declaration {
+        variant,
+        announcement: Announcement { hand: true, ..Default::default() },
+    }
+}
+
+///Pairs of the declarer's strategy and the defenders' strategy, a single strategy plays against itself
+fn get_lineups(strategies: usize) -> Vec<(usize, usize)> {
+    if strategies == 1 {
+        return vec![(0, 0)];
+    }
+    (0..strategies)
+        .flat_map(|declarer| (0..strategies).filter(move |defender| *defender != declarer).map(move |defender| (declarer, defender)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use skat_solver::solver::bitboard::{BitCard, BitCards};
+    use skat_solver::solver::bitstates::{BitGlobal, BitLocal};
+    use skat_solver::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use skat_solver::solver::{Player, Variant};
+    use crate::agent::{DoubleDummyAgent, PlayerAgent, RandomAgent};
+    use crate::arena::{Arena, get_deal, get_declaration, get_seat_hands, Statistic, Strategy};
+
+    #[test]
+    fn statistic() {
+        let mut statistic = Statistic::default();
+        for value in [1, 2, 3, 4] {
+            statistic.add(value);
+        }
+        assert_eq!(statistic.mean(), 2.5);
+        assert!((statistic.confidence_interval() - 1.96 * (5.0_f64 / 12.0).sqrt()).abs() < 1e-9);
+        let mut merged = Statistic::default();
+        merged.add(1);
+        let mut rest = Statistic::default();
+        for value in [2, 3, 4] {
+            rest.add(value);
+        }
+        merged.merge(&rest);
+        assert_eq!(merged, statistic);
+    }
+
+    #[test]
+    fn declarations() {
+        //three jacks and three Karo cards against two Hearts cards
+        let hand = [31, 30, 29, 0, 1, 2, 7, 8].iter().fold(BitCards(0), |cards, bit| cards | BitCard(1 << bit));
+        assert_eq!(get_declaration(hand).variant, Variant::Diamonds);
+        assert!(get_declaration(hand).announcement.hand);
+        //only jacks, every suit has as many trumps
+        assert_eq!(get_declaration(BitCards(0b1111 << 28)).variant, Variant::Clubs);
+        assert_eq!(get_deal(4, 17), get_deal(4, 17));
+        assert_ne!(get_deal(4, 17), get_deal(4, 18));
+    }
+
+    fn create_random(_hands: [BitCards; 3], seed: u64) -> Box<dyn PlayerAgent> {
+        Box::new(RandomAgent::new(seed))
+    }
+
+    #[test]
+    fn random_arena() {
+        let strategies = || vec![
+            Strategy { name: "first".to_string(), create: create_random },
+            Strategy { name: "second".to_string(), create: create_random },
+        ];
+        let report = Arena::new(strategies(), 20, 7).with_threads(1).run().unwrap();
+        //the work is shared differently, but every deal is the same
+        assert_eq!(report, Arena::new(strategies(), 20, 7).with_threads(3).run().unwrap());
+        for (strategy, other) in [(0, 1), (1, 0)] {
+            let declarer = &report.strategies[strategy].declarer;
+            let defender = &report.strategies[other].defender;
+            assert_eq!(declarer.wins.count, 60);
+            assert_eq!(declarer.wins.sum + defender.wins.sum, 60);
+            assert_eq!(declarer.points.sum + defender.points.sum, 120 * 60);
+            assert_eq!(declarer.game_value.sum, -defender.game_value.sum);
+        }
+        //the largest seeds wrap around
+        assert!(Arena::new(strategies(), 2, u64::MAX).run().is_ok());
+    }
+
+    #[test]
+    fn double_dummy_arena() {
+        let strategies = vec![Strategy { name: "double dummy".to_string(), create: |hands, _| Box::new(DoubleDummyAgent::new(hands)) }];
+        let report = Arena::new(strategies, 1, 2).with_threads(1).run().unwrap();
+        //perfect play on every side gets the solved points of the deal
+        let (hands, _) = get_deal(2, 0);
+        let variant = get_declaration(hands[0]).variant;
+        let points: Vec<i64> = [Player::One, Player::Two, Player::Three].into_iter()
+            .map(|seat| {
+                let [p1, p2, p3] = get_seat_hands(hands, seat);
+                assert_eq!([p1, p2, p3][seat as usize], hands[0]);
+                let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, seat, variant));
+                solver.solve(BitLocal::new((p1 | p2 | p3).0, Player::One, &solver.global_state)) as i64
+            })
+            .collect();
+        let declarer = &report.strategies[0].declarer;
+        assert_eq!(declarer.points.sum, points.iter().sum::<i64>());
+        assert_eq!(declarer.wins.sum, points.iter().filter(|points| **points > 60).count() as i64);
+    }
+}
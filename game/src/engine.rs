@@ -0,0 +1,368 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use skat_solver::bidding::{AuctionResult, BiddingStrategy, Position, run_auction};
+use skat_solver::pimc::PlayerView;
+use skat_solver::scoring::{Announcement, calculate_game_value, GameResult, Outcome};
+use skat_solver::solver::bitboard::{BitCard, BitCards, calculate_who_won_better};
+use skat_solver::solver::{calculate_current_suit_mask, calculate_next_moves, GlobalState, Player, Variant};
+use crate::agent::PlayerAgent;
+
+///Variant and announcements of the declarer, for null games hand and ouvert are part of the variant
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Declaration {
+    pub variant: Variant,
+    pub announcement: Announcement,
+}
+
+impl Declaration {
+    ///Schneider, Schwarz and Ouvert can only be announced in hand games
+    pub fn is_valid(&self, hand_game: bool) -> bool {
+        let announcement = self.announcement;
+        if announcement.hand != hand_game {
+            return false;
+        }
+        match self.variant {
+            Variant::Ramsch => false,
+            Variant::Null | Variant::NullOuvert => !hand_game && !announcement.schneider && !announcement.schwarz && !announcement.ouvert,
+            Variant::NullHand | Variant::NullOuvertHand => hand_game && !announcement.schneider && !announcement.schwarz && !announcement.ouvert,
+            _ => hand_game || (!announcement.schneider && !announcement.schwarz && !announcement.ouvert),
+        }
+    }
+
+    ///The declarer plays with an open hand
+    pub fn is_ouvert(&self) -> bool {
+        matches!(self.variant, Variant::NullOuvert | Variant::NullOuvertHand) || self.announcement.ouvert
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Trick {
+    pub leader: Player,
+    ///in the order they were played
+    pub cards: [BitCard; 3],
+    pub winner: Player,
+}
+
+///Everything after the auction, if somebody became declarer
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayedGame {
+    pub declarer: Player,
+    pub declaration: Declaration,
+    ///the hand of the declarer after the skat was picked up and put down
+    pub declarer_hand: BitCards,
+    ///the skat which counts for the declarer, the discarded cards if the skat was picked up
+    pub skat: BitCards,
+    pub tricks: Vec<Trick>,
+    pub outcome: Outcome,
+    pub result: GameResult,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub vorhand: Player,
+    ///the dealt hands, indexed by the players
+    pub hands: [BitCards; 3],
+    ///the dealt skat
+    pub skat: BitCards,
+    pub auction: AuctionResult,
+    ///none if every player passed
+    pub game: Option<PlayedGame>,
+}
+
+///Decisions of an agent which break the rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameError {
+    ///the discarded cards are not two cards of the hand with the skat
+    Discard(Player, BitCards),
+    Declaration(Player, Declaration),
+    ///the card is not one of the legal cards of the player
+    Card(Player, BitCard),
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::Discard(player, cards) => write!(f, "{player:?} can not discard {cards:?}"),
+            GameError::Declaration(player, declaration) => write!(f, "{player:?} can not declare {declaration:?}"),
+            GameError::Card(player, card) => write!(f, "{player:?} can not play {}", card.get_human_representation()),
+        }
+    }
+}
+
+impl Error for GameError {}
+
+///Shuffles all 32 cards into three hands of ten cards and the skat
+pub fn deal_cards<R: Rng>(rng: &mut R) -> ([BitCards; 3], BitCards) {
+    let mut cards: Vec<BitCard> = BitCards(u32::MAX).collect();
+    cards.shuffle(rng);
+    let collect = |cards: &[BitCard]| cards.iter().fold(BitCards(0), |cards, card| cards | *card);
+    ([collect(&cards[..10]), collect(&cards[10..20]), collect(&cards[20..30])], collect(&cards[30..]))
+}
+
+///
+/// Plays one game from the auction to the scoring. Agents are indexed by the players,
+/// Vorhand plays the first card.
+pub fn play_game(hands: [BitCards; 3], skat: BitCards, vorhand: Player, agents: &mut [&mut dyn PlayerAgent; 3]) -> Result<GameRecord, GameError> {
+    let auction = {
+        let [first, second, third] = agents;
+        let strategies: &mut [&mut dyn BiddingStrategy; 3] = &mut [*first, *second, *third];
+        run_auction(hands, vorhand, strategies)
+    };
+    let mut record = GameRecord {
+        vorhand,
+        hands,
+        skat,
+        auction,
+        game: None,
+    };
+    let Some(declarer) = auction.declarer else {
+        return Ok(record);
+    };
+    let position = [Position::Vorhand, Position::Mittelhand, Position::Hinterhand].into_iter()
+        .find(|position| position.get_player(vorhand) == declarer)
+        .unwrap();
+    let agent = &mut agents[declarer as usize];
+    let mut player_cards = hands;
+    let mut game_skat = skat;
+    let hand_game = !agent.pick_up_skat(hands[declarer as usize], position, auction.bid);
+    if !hand_game {
+        let cards = hands[declarer as usize] | skat;
+        let discard = agent.discard(cards, position, auction.bid);
+        if discard.0.count_ones() != 2 || discard.0 & !cards.0 != 0 {
+            return Err(GameError::Discard(declarer, discard));
+        }
+        player_cards[declarer as usize] = BitCards(cards.0 & !discard.0);
+        game_skat = discard;
+    }
+    let declaration = agent.declare(player_cards[declarer as usize], position, hand_game, auction.bid);
+    if !declaration.is_valid(hand_game) {
+        return Err(GameError::Declaration(declarer, declaration));
+    }
+    record.game = Some(play_declared_game(player_cards, game_skat, vorhand, declarer, declaration, auction.bid, agents)?);
+    Ok(record)
+}
+
+///
+/// Plays and scores a game which is already declared, the hands and the skat are the ones
+/// after the skat was put down. A bid of zero can not be overbid.
+pub fn play_declared_game(hands: [BitCards; 3], skat: BitCards, vorhand: Player, declarer: Player, declaration: Declaration, bid: u16, agents: &mut [&mut dyn PlayerAgent; 3]) -> Result<PlayedGame, GameError> {
+    let hand_game = declaration.announcement.hand;
+    if !declaration.is_valid(hand_game) {
+        return Err(GameError::Declaration(declarer, declaration));
+    }
+    let (tricks, outcome) = play_tricks(hands, skat, vorhand, declarer, declaration, agents)?;
+    let global_state = GlobalState::new((hands[0], hands[1], hands[2]), skat, declarer, declaration.variant);
+    let result = calculate_game_value(&global_state, &declaration.announcement, &outcome, bid).expect("the declaration was checked");
+    Ok(PlayedGame {
+        declarer,
+        declaration,
+        declarer_hand: hands[declarer as usize],
+        skat,
+        tricks,
+        outcome,
+        result,
+    })
+}
+
+///
+/// Plays the tricks until all cards are gone or the declarer of a null game takes a trick.
+/// The defenders of an ouvert game know the hand of the declarer.
+fn play_tricks(mut hands: [BitCards; 3], skat: BitCards, vorhand: Player, declarer: Player, declaration: Declaration, agents: &mut [&mut dyn PlayerAgent; 3]) -> Result<(Vec<Trick>, Outcome), GameError> {
+    let variant = declaration.variant;
+    let mut views = [Player::One, Player::Two, Player::Three].map(|player| {
+        let view = PlayerView::new(player, declarer, vorhand, variant, hands[player as usize]);
+        //only the declarer knows the skat, if it was picked up, the defenders see an open hand
+        if player == declarer && !declaration.announcement.hand {
+            view.with_skat(skat)
+        } else if player != declarer && declaration.is_ouvert() {
+            view.with_known_cards(declarer, hands[declarer as usize])
+        } else {
+            view
+        }
+    });
+    let mut tricks = Vec::new();
+    let mut declarer_points = if variant.is_null() { 0 } else { skat.get_cards_points() };
+    let mut declarer_tricks = 0;
+    let mut leader = vorhand;
+    while hands[0].0 != 0 {
+        let mut cards = [BitCard(0); 3];
+        let mut player = leader;
+        for i in 0..3 {
+            let suit_mask = if i == 0 { None } else { Some(calculate_current_suit_mask(cards[0], &variant)) };
+            let legal_cards = calculate_next_moves(hands[player as usize], suit_mask);
+            let card = agents[player as usize].play_card(&views[player as usize], legal_cards);
+            if card.0.count_ones() != 1 || card.0 & legal_cards.0 == 0 {
+                return Err(GameError::Card(player, card));
+            }
+            hands[player as usize] = BitCards(hands[player as usize].0 & !card.0);
+            for view in &mut views {
+                view.play(card).map_err(|_| GameError::Card(player, card))?;
+            }
+            cards[i] = card;
+            player = player.get_next_player();
+        }
+        let (winner_card, points) = calculate_who_won_better(cards[0], cards[1], cards[2], &variant);
+        let winner = [leader, leader.get_next_player(), leader.get_previous_player()][cards.iter().position(|card| *card == winner_card).unwrap()];
+        if winner == declarer {
+            declarer_tricks += 1;
+            if !variant.is_null() {
+                declarer_points += points;
+            }
+        }
+        let trick = Trick { leader, cards, winner };
+        for player in [Player::One, Player::Two, Player::Three] {
+            agents[player as usize].trick_finished(&views[player as usize], &trick);
+        }
+        tricks.push(trick);
+        leader = winner;
+        if variant.is_null() && declarer_tricks > 0 {
+            break;
+        }
+    }
+    let outcome = Outcome::new(declarer_points, declarer_tricks, tricks.len() as u8);
+    Ok((tricks, outcome))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use skat_solver::bidding::{BiddingStrategy, Position};
+    use skat_solver::pimc::PlayerView;
+    use skat_solver::scoring::Announcement;
+    use skat_solver::solver::bitboard::{BitCard, BitCards};
+    use skat_solver::solver::{Player, Variant};
+    use crate::agent::{PlayerAgent, RandomAgent};
+    use crate::engine::{deal_cards, Declaration, GameError, play_declared_game, play_game};
+
+    #[test]
+    fn dealing() {
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for _ in 0..100 {
+            let (hands, skat) = deal_cards(&mut rng);
+            assert!(hands.iter().all(|hand| hand.0.count_ones() == 10));
+            assert_eq!(skat.0.count_ones(), 2);
+            assert_eq!((hands[0] | hands[1] | hands[2] | skat).0, u32::MAX);
+        }
+    }
+
+    #[test]
+    fn declarations() {
+        let declaration = |variant, hand, schneider, ouvert| Declaration {
+            variant,
+            announcement: Announcement { hand, schneider, schwarz: false, ouvert },
+        };
+        assert!(declaration(Variant::Clubs, false, false, false).is_valid(false));
+        assert!(declaration(Variant::Grand, true, true, false).is_valid(true));
+        assert!(!declaration(Variant::Grand, false, true, false).is_valid(false));
+        assert!(!declaration(Variant::Grand, false, false, false).is_valid(true));
+        assert!(declaration(Variant::NullOuvert, false, false, false).is_valid(false));
+        assert!(!declaration(Variant::NullOuvert, true, false, false).is_valid(true));
+        assert!(!declaration(Variant::NullHand, true, false, true).is_valid(true));
+        assert!(!declaration(Variant::Ramsch, false, false, false).is_valid(false));
+        //games which are declared before are checked as well
+        let (hands, skat) = deal_cards(&mut ChaCha8Rng::seed_from_u64(0));
+        let mut agents = [RandomAgent::new(0), RandomAgent::new(1), RandomAgent::new(2)];
+        let [first, second, third] = &mut agents;
+        let ramsch = declaration(Variant::Ramsch, true, false, false);
+        let result = play_declared_game(hands, skat, Player::One, Player::Two, ramsch, 18, &mut [first as &mut dyn PlayerAgent, second, third]);
+        assert_eq!(result.unwrap_err(), GameError::Declaration(Player::Two, ramsch));
+    }
+
+    ///Plays the first legal card and keeps the declarer's cards of a deal sampled from the own view
+    struct ObservingAgent {
+        declarer: Player,
+        rng: ChaCha8Rng,
+        samples: Vec<(BitCards, BitCards)>,
+    }
+
+    impl BiddingStrategy for ObservingAgent {
+        fn bid(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+            false
+        }
+
+        fn hold(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+            false
+        }
+    }
+
+    impl PlayerAgent for ObservingAgent {
+        fn pick_up_skat(&mut self, _hand: BitCards, _position: Position, _bid: u16) -> bool {
+            unreachable!("the game is declared before")
+        }
+
+        fn discard(&mut self, _cards: BitCards, _position: Position, _bid: u16) -> BitCards {
+            unreachable!("the game is declared before")
+        }
+
+        fn declare(&mut self, _hand: BitCards, _position: Position, _hand_game: bool, _bid: u16) -> Declaration {
+            unreachable!("the game is declared before")
+        }
+
+        fn play_card(&mut self, view: &PlayerView, legal_cards: BitCards) -> BitCard {
+            let deal = view.get_knowledge().sample_deal(&mut self.rng).unwrap();
+            let played = view.played_cards.iter().fold(BitCards(0), |cards, card| cards | *card);
+            self.samples.push((deal.hands[self.declarer as usize], played));
+            legal_cards.into_iter().next().unwrap()
+        }
+    }
+
+    #[test]
+    fn ouvert_hand_known() {
+        let (hands, skat) = deal_cards(&mut ChaCha8Rng::seed_from_u64(5));
+        for (variant, hand, ouvert) in [(Variant::NullOuvert, false, false), (Variant::NullOuvertHand, true, false), (Variant::Grand, true, true), (Variant::Null, false, false)] {
+            let declaration = Declaration { variant, announcement: Announcement { hand, ouvert, ..Default::default() } };
+            let mut declarer = RandomAgent::new(0);
+            let mut first = ObservingAgent { declarer: Player::One, rng: ChaCha8Rng::seed_from_u64(1), samples: Vec::new() };
+            let mut second = ObservingAgent { declarer: Player::One, rng: ChaCha8Rng::seed_from_u64(2), samples: Vec::new() };
+            play_declared_game(hands, skat, Player::One, Player::One, declaration, 0, &mut [&mut declarer, &mut first, &mut second]).unwrap();
+            //the defenders only sample the real hand of an ouvert declarer
+            let known = first.samples.iter().chain(&second.samples).all(|(sample, played)| sample.0 == hands[0].0 & !played.0);
+            assert_eq!(known, declaration.is_ouvert(), "{variant:?}");
+            assert!(!first.samples.is_empty() && !second.samples.is_empty());
+        }
+    }
+
+    #[test]
+    fn random_games() {
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let mut played = 0;
+        for seed in 0..200 {
+            let (hands, skat) = deal_cards(&mut rng);
+            let vorhand = Player::from((seed % 3) as u8);
+            let mut agents = [RandomAgent::new(seed), RandomAgent::new(seed + 1000), RandomAgent::new(seed + 2000)];
+            let [first, second, third] = &mut agents;
+            let record = play_game(hands, skat, vorhand, &mut [first as &mut dyn PlayerAgent, second, third]).unwrap();
+            let Some(game) = record.game else {
+                assert_eq!(record.auction.declarer, None);
+                continue;
+            };
+            played += 1;
+            assert_eq!(Some(game.declarer), record.auction.declarer);
+            assert_eq!((game.declarer_hand | game.skat).0, (hands[game.declarer as usize] | skat).0);
+            //every card is played once and the winner leads the next trick
+            let played_cards = game.tricks.iter().flat_map(|trick| trick.cards).fold(BitCards(0), |cards, card| cards | card);
+            assert_eq!(played_cards.0.count_ones() as usize, 3 * game.tricks.len());
+            assert_eq!(game.tricks[0].leader, vorhand);
+            assert!(game.tricks.windows(2).all(|pair| pair[0].winner == pair[1].leader));
+            if game.declaration.variant.is_null() {
+                assert!(game.tricks.len() == 10 || game.outcome.declarer_tricks == 1);
+                assert_eq!(game.result.won, game.outcome.declarer_tricks == 0 && !game.result.overbid);
+            } else {
+                assert_eq!(game.tricks.len(), 10);
+                assert_eq!(played_cards.0 | game.skat.0, u32::MAX);
+                let defender_points: u8 = game.tricks.iter()
+                    .filter(|trick| trick.winner != game.declarer)
+                    .flat_map(|trick| trick.cards)
+                    .map(|card| card.get_point())
+                    .sum();
+                assert_eq!(game.outcome.declarer_points + defender_points, 120);
+            }
+            assert!(game.result.game_value >= record.auction.bid);
+        }
+        assert!(played > 100);
+    }
+}
@@ -0,0 +1,286 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use skat_solver::dataset::{DatasetWriter, SolvedPosition};
+use skat_solver::solver::bitboard::{BitCard, BitCards};
+use skat_solver::solver::bitstates::{BitGlobal, BitLocal};
+use skat_solver::solver::synchronus::ab_tt::DefaultSolver;
+use skat_solver::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+use skat_solver::solver::synchronus::ab_tt_optimized::EnhancedSolver;
+use skat_solver::solver::synchronus::local_state::LState;
+use skat_solver::solver::{GlobalState, Player, Variant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolverKind {
+    Default,
+    Enhanced,
+    MoreEnhanced,
+}
+
+impl SolverKind {
+    ///Name of the option, which is written as the solver of the positions
+    pub fn get_name(self) -> &'static str {
+        match self {
+            SolverKind::Default => "default",
+            SolverKind::Enhanced => "enhanced",
+            SolverKind::MoreEnhanced => "more-enhanced",
+        }
+    }
+}
+
+///
+/// Settings of a generated data file. Every deal is solved for every variant and every
+/// player to move, so a file has deals * variants * 3 lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeneratorConfig {
+    ///cards of every hand
+    pub cards: usize,
+    pub deals: usize,
+    pub variants: Vec<Variant>,
+    pub declarer: Player,
+    pub seed: u64,
+    pub solver: SolverKind,
+    pub output: String,
+    ///deal two of the remaining cards into the skat, their points count for the declarer
+    pub real_skat: bool,
+    ///write the legacy format without a header instead of the current version
+    pub legacy: bool,
+    pub threads: usize,
+    pub progress: bool,
+}
+
+const NUMBERS: [&str; 11] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten"];
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            cards: 8,
+            deals: 10,
+            variants: vec![Variant::Grand, Variant::Clubs, Variant::Spades, Variant::Hearts, Variant::Diamonds],
+            declarer: Player::One,
+            seed: 0,
+            solver: SolverKind::Default,
+            output: "eight_cards.txt".to_string(),
+            real_skat: false,
+            legacy: false,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            progress: true,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    ///
+    /// Reads options like --cards 6 --deals 100 --variants grand,null --declarer 2 --seed 7
+    /// --solver more-enhanced --output six_cards.txt --real-skat --legacy --threads 4 --quiet.
+    /// Without --output the file is named after the amount of cards.
+    pub fn from_args(args: &[String]) -> Result<GeneratorConfig, String> {
+        let mut config = GeneratorConfig::default();
+        let mut output = None;
+        let mut args = args.iter();
+        while let Some(option) = args.next() {
+            let mut value = || args.next().ok_or(format!("{option} needs a value"));
+            match option.as_str() {
+                "--cards" => config.cards = parse_number(value()?, 1, 10)?,
+                "--deals" => config.deals = parse_number(value()?, 1, usize::MAX)?,
+                "--variants" => config.variants = value()?.split(',').map(parse_variant).collect::<Result<_, _>>()?,
+                "--declarer" => config.declarer = Player::from(parse_number(value()?, 1, 3)? as u8 - 1),
+                "--seed" => config.seed = value()?.parse().map_err(|_| "the seed is not a number".to_string())?,
+                "--solver" => {
+                    config.solver = match value()?.as_str() {
+                        "default" => SolverKind::Default,
+                        "enhanced" => SolverKind::Enhanced,
+                        "more-enhanced" => SolverKind::MoreEnhanced,
+                        solver => return Err(format!("unknown solver {solver}")),
+                    }
+                }
+                "--output" => output = Some(value()?.clone()),
+                "--real-skat" => config.real_skat = true,
+                "--legacy" => config.legacy = true,
+                "--threads" => config.threads = parse_number(value()?, 1, usize::MAX)?,
+                "--quiet" => config.progress = false,
+                option => return Err(format!("unknown option {option}")),
+            }
+        }
+        config.output = output.unwrap_or_else(|| format!("{}_cards.txt", NUMBERS[config.cards]));
+        Ok(config)
+    }
+
+    pub fn get_lines(&self) -> usize {
+        self.deals * self.variants.len() * 3
+    }
+}
+
+pub(crate) fn parse_number(value: &str, min: usize, max: usize) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(format!("{value} is not a number from {min} to {max}")),
+    }
+}
+
+fn parse_variant(name: &str) -> Result<Variant, String> {
+    match name {
+        "grand" => Ok(Variant::Grand),
+        "clubs" => Ok(Variant::Clubs),
+        "spades" => Ok(Variant::Spades),
+        "hearts" => Ok(Variant::Hearts),
+        "diamonds" => Ok(Variant::Diamonds),
+        "null" => Ok(Variant::Null),
+        _ => Err(format!("unknown variant {name}")),
+    }
+}
+
+///The position in the given line of the file
+pub fn generate_position(config: &GeneratorConfig, number: usize) -> SolvedPosition {
+    //every deal has its own stream, so the file does not depend on the threads
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+    rng.set_stream((number / (3 * config.variants.len())) as u64);
+    let variant = config.variants[number / 3 % config.variants.len()];
+    let current_player = Player::from((number % 3) as u8);
+    let mut cards: Vec<BitCard> = BitCards(u32::MAX).collect();
+    cards.shuffle(&mut rng);
+    let collect = |cards: &[BitCard]| cards.iter().fold(BitCards(0), |cards, card| cards | *card);
+    let amount = config.cards;
+    let hands = [collect(&cards[..amount]), collect(&cards[amount..2 * amount]), collect(&cards[2 * amount..3 * amount])];
+    let skat = if config.real_skat { collect(&cards[3 * amount..3 * amount + 2]) } else { BitCards(0) };
+    let result = solve(config.solver, hands, skat, config.declarer, current_player, variant);
+    let mut position = SolvedPosition::new((hands[0], hands[1], hands[2]), skat, current_player, variant, result);
+    position.declarer = config.declarer;
+    position.solver = Some(config.solver.get_name().to_string());
+    position
+}
+
+///Card points of the declarer including the points of the skat, tricks of the declarer in null games
+pub fn solve(solver: SolverKind, hands: [BitCards; 3], skat: BitCards, declarer: Player, current_player: Player, variant: Variant) -> u8 {
+    let all_cards = hands[0] | hands[1] | hands[2];
+    match solver {
+        SolverKind::Default => {
+            let global_state = GlobalState::new((hands[0], hands[1], hands[2]), skat, declarer, variant);
+            DefaultSolver::new(global_state).solve(LState::new(all_cards, current_player)) as u8
+        }
+        SolverKind::Enhanced => {
+            let global_state = GlobalState::new((hands[0], hands[1], hands[2]), skat, declarer, variant);
+            EnhancedSolver::new(global_state).solve(LState::new(all_cards, current_player))
+        }
+        SolverKind::MoreEnhanced => {
+            let mut solver = MoreEnhancedSolver::new(BitGlobal::new(hands[0], hands[1], hands[2], declarer, variant));
+            let score = solver.solve(BitLocal::new(all_cards.0, current_player, &solver.global_state));
+            //the global state counts the missing cards as skat if all other cards are in the hands
+            let skat_points = if variant.is_null() { 0 } else { skat.get_cards_points() };
+            score - solver.global_state.skat_points + skat_points
+        }
+    }
+}
+
+///Solves all positions on the threads and writes them in order
+pub fn generate(config: &GeneratorConfig) -> io::Result<()> {
+    let lines = config.get_lines();
+    let next_line = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; lines]);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..config.threads).map(|_| scope.spawn(|| loop {
+                let number = next_line.fetch_add(1, Ordering::Relaxed);
+                if number >= lines {
+                    break;
+                }
+                let position = generate_position(config, number);
+                results.lock().unwrap()[number] = Some(position);
+                done.fetch_add(1, Ordering::Relaxed);
+            })).collect();
+        if config.progress {
+            //a worker which panicked stops counting, the panic is passed on by the scope
+            while !workers.iter().all(|worker| worker.is_finished()) {
+                eprint!("\r{}/{lines} positions solved", done.load(Ordering::Relaxed));
+                thread::sleep(Duration::from_millis(200));
+            }
+            eprintln!("\r{}/{lines} positions solved", done.load(Ordering::Relaxed));
+        }
+    });
+    let file = BufWriter::new(File::create(&config.output)?);
+    let mut writer = if config.legacy { DatasetWriter::legacy(file) } else { DatasetWriter::new(file)? };
+    for position in results.into_inner().unwrap().into_iter().flatten() {
+        writer.write(&position)?;
+    }
+    writer.into_inner().flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use skat_solver::dataset::{DatasetReader, read_dataset};
+    use skat_solver::solver::{Player, Variant};
+    use crate::generator::{generate, generate_position, GeneratorConfig, SolverKind};
+
+    fn to_args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn generator_args() {
+        let config = GeneratorConfig::from_args(&to_args("--cards 4 --deals 3 --variants grand,null --declarer 2 --seed 9 --solver more-enhanced --real-skat --legacy --threads 2 --quiet")).unwrap();
+        assert_eq!(config.cards, 4);
+        assert_eq!(config.variants, [Variant::Grand, Variant::Null]);
+        assert_eq!(config.declarer, Player::Two);
+        assert_eq!(config.solver, SolverKind::MoreEnhanced);
+        assert_eq!(config.output, "four_cards.txt");
+        assert!(config.real_skat && config.legacy && !config.progress);
+        assert_eq!(config.get_lines(), 18);
+        assert!(GeneratorConfig::from_args(&to_args("--cards 11")).is_err());
+        assert!(GeneratorConfig::from_args(&to_args("--variants ramsch")).is_err());
+        assert!(GeneratorConfig::from_args(&to_args("--seed")).is_err());
+    }
+
+    #[test]
+    fn generator_solvers_agree() {
+        let config = GeneratorConfig::from_args(&to_args("--cards 4 --deals 4 --variants grand,hearts,null --declarer 3 --seed 5 --real-skat")).unwrap();
+        for number in 0..config.get_lines() {
+            let position = generate_position(&config, number);
+            assert_eq!(position.declarer, Player::Three);
+            assert_eq!(position.solver.as_deref(), Some("default"));
+            let (p1, p2, p3) = position.player_cards;
+            assert_eq!(position.skat.0.count_ones(), 2);
+            assert_eq!((p1 | p2 | p3 | position.skat).0.count_ones(), 14);
+            for solver in [SolverKind::Enhanced, SolverKind::MoreEnhanced] {
+                let other = generate_position(&GeneratorConfig { solver, ..config.clone() }, number);
+                assert_eq!(other.solver.as_deref(), Some(solver.get_name()));
+                assert_eq!(other.result, position.result);
+                assert_eq!(other.player_cards, position.player_cards);
+            }
+        }
+    }
+
+    #[test]
+    fn generator_file() {
+        let path = std::env::temp_dir().join("generator_file.txt");
+        let output = path.to_str().unwrap();
+        let config = GeneratorConfig::from_args(&to_args(&format!("--cards 3 --deals 5 --seed 1 --threads 3 --quiet --output {output}"))).unwrap();
+        generate(&config).unwrap();
+        let input = fs::read_to_string(&path).unwrap();
+        assert_eq!(DatasetReader::new(input.as_bytes()).unwrap().get_version(), Some(1));
+        let positions = read_dataset(&path).unwrap();
+        assert_eq!(positions.len(), 75);
+        for (number, position) in positions.iter().enumerate() {
+            assert_eq!(*position, generate_position(&config, number));
+            assert_eq!(position.current_player as usize, number % 3);
+            //the deal is the same for every variant and player to move
+            assert_eq!(position.player_cards, positions[number - number % 15].player_cards);
+        }
+        //the legacy format without the declarer, the skat and the solver
+        generate(&GeneratorConfig { legacy: true, ..config.clone() }).unwrap();
+        let input = fs::read_to_string(&path).unwrap();
+        assert_eq!(input.lines().count(), 75);
+        for (line, position) in input.lines().zip(&positions) {
+            let (p1, p2, p3) = position.player_cards;
+            assert_eq!(line, format!("{},{},{},0,{},{},{}", p1.0, p2.0, p3.0, position.current_player as u8, position.variant as u8, position.result));
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
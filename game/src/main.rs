@@ -1,59 +1,133 @@
 
+mod agent;
+mod arena;
+mod engine;
+mod generator;
+mod terminal;
+
+use std::env;
+use std::error::Error;
 use std::fs::File;
-use std::io::Write;
-use rand::prelude::SliceRandom;
-use rand::thread_rng;
-use skat_solver::solver::bitboard::{BitCard, BitCards};
-use skat_solver::solver::{GlobalState, Player, Variant};
-use skat_solver::solver::synchronus::ab_tt::DefaultSolver;
-use skat_solver::solver::synchronus::local_state::LState;
+use std::io::{BufReader, BufWriter, Write};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use skat_solver::binary_dataset::{binary_to_text, text_to_binary};
+use skat_solver::dataset::DatasetWriter;
+use skat_solver::solver::Player;
+use crate::agent::{DoubleDummyAgent, PlayerAgent, RandomAgent, SolverAgent};
+use crate::arena::{Arena, Strategy};
+use crate::engine::{deal_cards, GameRecord, play_game};
+use crate::generator::{generate, GeneratorConfig, parse_number};
+use crate::terminal::play_terminal;
 
 fn main() {
-    create_n_cards(10, 8);
-}
-
-fn create_cards(cards: &[u32]) -> BitCards {
-    let mut empty = BitCards(0);
-    for card in cards {
-        empty = empty | BitCard(2_u32.pow(*card))
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        //game terminal [seat 1-3] [seed]
+        Some("terminal") => {
+            let seat = args.get(2).map_or(Ok(1), |seat| parse_number(seat, 1, 3));
+            let seed = args.get(3).map_or(Ok(0), |seed| seed.parse::<u64>().map_err(|_| format!("{seed} is not a seed")));
+            match (seat, seed) {
+                (Ok(seat), Ok(seed)) => play_terminal(Player::from(seat as u8 - 1), seed, 4),
+                (Err(error), _) | (_, Err(error)) => eprintln!("{error}\nusage: game terminal [seat 1-3] [seed]"),
+            }
+        }
+        //game play <solver|random> [seed]
+        Some("play") => {
+            let seed = args.get(3).map_or(0, |seed| seed.parse::<u64>().expect("the seed is not a number"));
+            let mut first: Box<dyn PlayerAgent> = match args.get(2).map(String::as_str) {
+                Some("random") => Box::new(RandomAgent::new(seed)),
+                _ => Box::new(SolverAgent::new(4, seed)),
+            };
+            let mut second = SolverAgent::new(4, seed + 1);
+            let mut third = SolverAgent::new(4, seed + 2);
+            let (hands, skat) = deal_cards(&mut ChaCha8Rng::seed_from_u64(seed));
+            match play_game(hands, skat, Player::One, &mut [first.as_mut(), &mut second, &mut third]) {
+                Ok(record) => print_record(&record),
+                Err(error) => eprintln!("{error}"),
+            }
+        }
+        //game arena <strategy> <strategy>... [deals] [seed] [threads]
+        Some("arena") => {
+            let strategies: Vec<Strategy> = args[2..].iter().map_while(|name| get_strategy(name)).collect();
+            let mut numbers = args[2 + strategies.len()..].iter().map(|number| number.parse::<u64>().expect("not a number"));
+            let deals = numbers.next().unwrap_or(1000) as usize;
+            let seed = numbers.next().unwrap_or(0);
+            let mut arena = Arena::new(strategies, deals, seed);
+            if let Some(threads) = numbers.next() {
+                arena = arena.with_threads(threads as usize);
+            }
+            match arena.run() {
+                Ok(report) => print!("{report}"),
+                Err(error) => eprintln!("{error}"),
+            }
+        }
+        //game generate [--cards n] [--deals n] [--variants grand,clubs,...] [--declarer 1-3] [--seed n]
+        //  [--solver default|enhanced|more-enhanced] [--output file] [--real-skat] [--legacy] [--threads n] [--quiet]
+        Some("generate") => {
+            match GeneratorConfig::from_args(&args[2..]) {
+                Ok(config) => if let Err(error) = generate(&config) {
+                    eprintln!("{}: {error}", config.output);
+                }
+                Err(error) => eprintln!("{error}"),
+            }
+        }
+        //game convert <input> <output> [--legacy], files ending with .bin are binary
+        Some("convert") => {
+            match convert(&args[2..]) {
+                Ok(records) => println!("{records} positions converted"),
+                Err(error) => eprintln!("{error}"),
+            }
+        }
+        _ => eprintln!("usage: game <terminal|play|arena|generate|convert> ..."),
     }
-    empty
 }
 
+///Strategies of the arena: random, double-dummy or pimc
+fn get_strategy(name: &str) -> Option<Strategy> {
+    let create = match name {
+        "random" => |_, seed| Box::new(RandomAgent::new(seed)) as Box<dyn PlayerAgent>,
+        "double-dummy" => |hands, _| Box::new(DoubleDummyAgent::new(hands)) as Box<dyn PlayerAgent>,
+        "pimc" => |_, seed| Box::new(SolverAgent::new(4, seed)) as Box<dyn PlayerAgent>,
+        _ => return None,
+    };
+    Some(Strategy {
+        name: name.to_string(),
+        create,
+    })
+}
 
-fn create_n_cards(n: usize, amount: usize) {
-    let mut rng = thread_rng();
-    let mut file = File::create("eight_cards.txt").unwrap();
-    for _ in 0..n {
-        for variant in [Variant::Grand, Variant::Clubs, Variant::Spades, Variant::Hearts, Variant::Diamonds] {
-            for current_player in [Player::One, Player::Two, Player::Three] {
-                let mut numbers: Vec<u32> = (0..32).collect();
-                numbers.shuffle(&mut rng);
-                let (first, rest) = numbers.split_at(amount);
-                let (second, rest) = rest.split_at(amount);
-                let (third, _) = rest.split_at(amount);
-                let p1 = create_cards(first).0;
-                let p2 = create_cards(second).0;
-                let p3 = create_cards(third).0;
-                let all = p1 | p2 | p3;
-
-                let local_state = LState::new(BitCards(p1 | p2 | p3), current_player);
-                let global_state = GlobalState::new((BitCards(p1), BitCards(p2), BitCards(p3)), BitCards(0), Player::One, variant);
-                let mut solver = DefaultSolver {
-                    global_state,
-                    look_up_table: Default::default(),
-                };
-                let result = solver.solve(local_state);
-                let current_player = current_player as u8;
-                let variant = variant as u8;
+///Converts text files into binary files and back, text is written in the versioned format unless --legacy is given
+fn convert(args: &[String]) -> Result<usize, Box<dyn Error>> {
+    let [input, output, options @ ..] = args else {
+        return Err("convert needs an input and an output file".into());
+    };
+    let reader = BufReader::new(File::open(input)?);
+    let writer = BufWriter::new(File::create(output)?);
+    let records = if output.ends_with(".bin") {
+        text_to_binary(reader, writer)?
+    } else {
+        let mut writer = if options.iter().any(|option| option == "--legacy") {
+            DatasetWriter::legacy(writer)
+        } else {
+            DatasetWriter::new(writer)?
+        };
+        let records = binary_to_text(reader, &mut writer)?;
+        writer.into_inner().flush()?;
+        records
+    };
+    Ok(records)
+}
 
-                assert_eq!(all.count_ones(), 3 * amount as u32);
-                assert_eq!(p1 & p2, 0);
-                assert_eq!(p1 & p3, 0);
-                assert_eq!(p2 & p3, 0);
-                let to_bet_printed = format!("{p1},{p2},{p3},0,{current_player},{variant},{result}\n");
-                file.write_all(to_bet_printed.as_bytes()).expect("");
-            }
-        }
+fn print_record(record: &GameRecord) {
+    let Some(game) = &record.game else {
+        println!("Every player passed");
+        return;
+    };
+    println!("{:?} plays {:?} for {}", game.declarer, game.declaration.variant, record.auction.bid);
+    for trick in &game.tricks {
+        let cards: Vec<String> = trick.cards.iter().map(|card| card.get_human_representation()).collect();
+        println!("{:?}: {} -> {:?}", trick.leader, cards.join(", "), trick.winner);
     }
-}
\ No newline at end of file
+    println!("{} points, {} tricks, game value {}, score {}", game.outcome.declarer_points, game.outcome.declarer_tricks, game.result.game_value, game.result.score);
+}
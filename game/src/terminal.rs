@@ -0,0 +1,344 @@
+use std::cmp::Ordering;
+use std::io::{BufRead, stdin, stdout, Write};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use skat_solver::bidding::{BiddingStrategy, Position};
+use skat_solver::pimc::PlayerView;
+use skat_solver::scoring::Announcement;
+use skat_solver::solver::bitboard::{BitCard, BitCards, calculate_who_won_better};
+use skat_solver::solver::{calculate_current_suit_mask, Player, Variant};
+use crate::agent::{PlayerAgent, SolverAgent};
+use crate::engine::{deal_cards, Declaration, GameRecord, play_game, Trick};
+
+///Trumps first, then the suits from Kreuz to Karo, every group from the highest card down
+pub fn sort_cards(cards: BitCards, variant: &Variant) -> Vec<BitCard> {
+    let mut sorted: Vec<BitCard> = cards.collect();
+    //the trump mask contains the jacks, so it is larger than every suit
+    sorted.sort_by(|a, b| {
+        let suit = calculate_current_suit_mask(*b, variant).0.cmp(&calculate_current_suit_mask(*a, variant).0);
+        suit.then_with(|| if a == b {
+            Ordering::Equal
+        } else if a.greater_than(*b, variant) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        })
+    });
+    sorted
+}
+
+fn format_cards(cards: &[BitCard]) -> String {
+    let cards: Vec<String> = cards.iter().map(|card| card.get_human_representation()).collect();
+    cards.join(", ")
+}
+
+///
+/// Asks a person in the terminal for every decision, wrong input is asked again.
+/// Once the input is closed nothing is shown and every question is answered with no or the first choice.
+pub struct HumanAgent<R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    closed: bool,
+}
+
+impl<R: BufRead, W: Write> HumanAgent<R, W> {
+    pub fn new(input: R, output: W) -> HumanAgent<R, W> {
+        HumanAgent {
+            input,
+            output,
+            closed: false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    ///The trimmed answer, none if the input was closed
+    fn ask(&mut self, question: &str) -> Option<String> {
+        if self.closed {
+            return None;
+        }
+        write!(self.output, "{question} ").unwrap();
+        self.output.flush().unwrap();
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                writeln!(self.output).unwrap();
+                self.closed = true;
+                None
+            }
+            Ok(_) => Some(line.trim().to_lowercase()),
+        }
+    }
+
+    fn ask_yes_no(&mut self, question: &str) -> bool {
+        loop {
+            match self.ask(&format!("{question} [y/n]")).as_deref() {
+                Some("y" | "yes") => return true,
+                Some("n" | "no") | None => return false,
+                _ => {}
+            }
+        }
+    }
+
+    ///Lists the cards with their numbers and asks for one of them
+    fn ask_card(&mut self, question: &str, cards: BitCards, variant: &Variant) -> BitCard {
+        let cards = sort_cards(cards, variant);
+        for (i, card) in cards.iter().enumerate().filter(|_| !self.closed) {
+            writeln!(self.output, "{i:>3}: {}", card.get_human_representation()).unwrap();
+        }
+        loop {
+            let Some(answer) = self.ask(question) else {
+                return cards[0];
+            };
+            if let Some(card) = answer.parse::<usize>().ok().and_then(|i| cards.get(i)) {
+                return *card;
+            }
+        }
+    }
+
+    ///Before the game is declared the cards are sorted like in a grand
+    fn show_hand(&mut self, hand: BitCards, variant: &Variant) {
+        if self.closed {
+            return;
+        }
+        writeln!(self.output, "Hand: {}", format_cards(&sort_cards(hand, variant))).unwrap();
+    }
+}
+
+impl<R: BufRead, W: Write> BiddingStrategy for HumanAgent<R, W> {
+    fn bid(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        self.show_hand(hand, &Variant::Grand);
+        self.ask_yes_no(&format!("{position:?}, do you bid {bid}?"))
+    }
+
+    fn hold(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        self.show_hand(hand, &Variant::Grand);
+        self.ask_yes_no(&format!("{position:?}, do you hold {bid}?"))
+    }
+}
+
+impl<R: BufRead, W: Write> PlayerAgent for HumanAgent<R, W> {
+    fn pick_up_skat(&mut self, hand: BitCards, _position: Position, bid: u16) -> bool {
+        self.show_hand(hand, &Variant::Grand);
+        self.ask_yes_no(&format!("You play for {bid}, do you pick up the skat?"))
+    }
+
+    fn discard(&mut self, cards: BitCards, _position: Position, _bid: u16) -> BitCards {
+        let first = self.ask_card("First card to put into the skat:", cards, &Variant::Grand);
+        let second = self.ask_card("Second card to put into the skat:", BitCards(cards.0 & !first.0), &Variant::Grand);
+        first | second
+    }
+
+    fn declare(&mut self, hand: BitCards, _position: Position, hand_game: bool, _bid: u16) -> Declaration {
+        self.show_hand(hand, &Variant::Grand);
+        let variant = loop {
+            let Some(answer) = self.ask("Game: grand, clubs, spades, hearts, diamonds, null or ouvert?") else {
+                break Variant::Grand;
+            };
+            let variant = match answer.as_str() {
+                "grand" => Variant::Grand,
+                "clubs" => Variant::Clubs,
+                "spades" => Variant::Spades,
+                "hearts" => Variant::Hearts,
+                "diamonds" => Variant::Diamonds,
+                "null" if hand_game => Variant::NullHand,
+                "null" => Variant::Null,
+                "ouvert" if hand_game => Variant::NullOuvertHand,
+                "ouvert" => Variant::NullOuvert,
+                _ => continue,
+            };
+            break variant;
+        };
+        let mut announcement = Announcement { hand: hand_game, ..Default::default() };
+        if hand_game && !variant.is_null() {
+            announcement.schneider = self.ask_yes_no("Do you announce schneider?");
+            announcement.schwarz = announcement.schneider && self.ask_yes_no("Do you announce schwarz?");
+            announcement.ouvert = announcement.schwarz && self.ask_yes_no("Do you play ouvert?");
+        }
+        Declaration {
+            variant,
+            announcement,
+        }
+    }
+
+    fn play_card(&mut self, view: &PlayerView, legal_cards: BitCards) -> BitCard {
+        if self.closed {
+            return self.ask_card("", legal_cards, &view.variant);
+        }
+        //every player plays once in the first trick
+        if view.played_cards.len() < 3 {
+            writeln!(self.output, "{:?} plays {:?}", view.declarer, view.variant).unwrap();
+        }
+        let table = view.played_cards.len() % 3;
+        if table > 0 {
+            let cards: Vec<String> = view.played_cards[view.played_cards.len() - table..].iter().enumerate()
+                .map(|(i, card)| {
+                    //the last card on the table belongs to the previous player
+                    let player = if table - i == 1 { view.player.get_previous_player() } else { view.player.get_next_player() };
+                    format!("{player:?}: {}", card.get_human_representation())
+                })
+                .collect();
+            writeln!(self.output, "Table: {}", cards.join(", ")).unwrap();
+        }
+        self.show_hand(view.hand, &view.variant);
+        self.ask_card("Your card:", legal_cards, &view.variant)
+    }
+
+    fn trick_finished(&mut self, view: &PlayerView, trick: &Trick) {
+        if self.closed {
+            return;
+        }
+        let (_, points) = calculate_who_won_better(trick.cards[0], trick.cards[1], trick.cards[2], &view.variant);
+        let players = [trick.leader, trick.leader.get_next_player(), trick.leader.get_previous_player()];
+        let cards: Vec<String> = players.iter().zip(trick.cards)
+            .map(|(player, card)| format!("{player:?}: {}", card.get_human_representation()))
+            .collect();
+        write!(self.output, "Trick {}: {} -> {:?}", view.played_cards.len() / 3, cards.join(", "), trick.winner).unwrap();
+        if view.variant.is_null() {
+            writeln!(self.output).unwrap();
+        } else {
+            writeln!(self.output, " takes {points} points").unwrap();
+        }
+    }
+}
+
+///
+/// Plays games in the terminal until the person stops or closes the input, the person takes the given seat
+/// and solver agents the other two. Vorhand moves on after every game.
+pub fn play_terminal(seat: Player, seed: u64, samples: usize) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut human = HumanAgent::new(stdin().lock(), stdout());
+    let mut first_bot = SolverAgent::new(samples, seed + 1);
+    let mut second_bot = SolverAgent::new(samples, seed + 2);
+    let mut scores = [0; 3];
+    let mut vorhand = Player::One;
+    loop {
+        let (hands, skat) = deal_cards(&mut rng);
+        let mut agents: [&mut dyn PlayerAgent; 3] = match seat {
+            Player::One => [&mut human, &mut first_bot, &mut second_bot],
+            Player::Two => [&mut first_bot, &mut human, &mut second_bot],
+            Player::Three => [&mut first_bot, &mut second_bot, &mut human],
+        };
+        println!("New game, {vorhand:?} is Vorhand, you are {seat:?}");
+        let result = play_game(hands, skat, vorhand, &mut agents);
+        //the rest of the game was played without the person
+        if human.is_closed() {
+            break;
+        }
+        match result {
+            Ok(record) => {
+                print_result(&record);
+                if let Some(game) = &record.game {
+                    scores[game.declarer as usize] += game.result.score as i32;
+                }
+            }
+            Err(error) => println!("{error}"),
+        }
+        println!("Scores: One {}, Two {}, Three {}", scores[0], scores[1], scores[2]);
+        if !human.ask_yes_no("Another game?") {
+            break;
+        }
+        vorhand = vorhand.get_next_player();
+    }
+}
+
+fn print_result(record: &GameRecord) {
+    let Some(game) = &record.game else {
+        println!("Every player passed");
+        return;
+    };
+    let hand = if game.declaration.announcement.hand { " Hand" } else { "" };
+    println!("{:?} played {:?}{hand} for {}, skat: {}", game.declarer, game.declaration.variant, record.auction.bid, format_cards(&sort_cards(game.skat, &game.declaration.variant)));
+    if game.declaration.variant.is_null() {
+        println!("The declarer took {} tricks", game.outcome.declarer_tricks);
+    } else {
+        println!("The declarer got {} points in {} tricks", game.outcome.declarer_points, game.outcome.declarer_tricks);
+    }
+    let result = if game.result.overbid { "lost, overbid" } else if game.result.won { "won" } else { "lost" };
+    println!("Game {result}, game value {}, score {}", game.result.game_value, game.result.score);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use skat_solver::bidding::{BiddingStrategy, Position};
+    use skat_solver::pimc::PlayerView;
+    use skat_solver::solver::bitboard::{BitCard, BitCards};
+    use skat_solver::solver::{Player, Variant};
+    use crate::agent::PlayerAgent;
+    use crate::engine::Trick;
+    use crate::terminal::{HumanAgent, sort_cards};
+
+    #[test]
+    fn sorted_cards() {
+        //Kreuz Jack, Karo Jack, Hearts Ass, 10 and 7, Kreuz 7 and Karo Ass
+        let cards = [31, 28, 13, 12, 7, 21, 6].iter().fold(BitCards(0), |cards, bit| cards | BitCard(1 << bit));
+        let bits = |variant: Variant| -> Vec<u32> { sort_cards(cards, &variant).iter().map(|card| card.0.ilog2()).collect() };
+        assert_eq!(bits(Variant::Hearts), [31, 28, 13, 12, 7, 21, 6]);
+        assert_eq!(bits(Variant::Grand), [31, 28, 21, 13, 12, 7, 6]);
+        assert_eq!(bits(Variant::Diamonds), [31, 28, 6, 21, 13, 12, 7]);
+        //in null games the jacks belong to their suits and the ten is below the jack
+        assert_eq!(bits(Variant::Null), [31, 21, 13, 12, 7, 6, 28]);
+    }
+
+    #[test]
+    fn human_agent_input() {
+        let hand = BitCards(0b111 << 10);
+        let input = Cursor::new("maybe\ny\n7\n1\n1\nn\nsolo\nhearts\nn\n");
+        let mut output = Vec::new();
+        let mut agent = HumanAgent::new(input, &mut output);
+        assert!(agent.bid(hand, Position::Mittelhand, 18));
+        //the cards are listed from the highest, wrong numbers are asked again
+        let discard = agent.discard(hand, Position::Mittelhand, 18);
+        assert_eq!(discard.0, 0b011 << 10);
+        assert!(!agent.pick_up_skat(hand, Position::Mittelhand, 18));
+        let declaration = agent.declare(hand, Position::Mittelhand, true, 18);
+        assert_eq!(declaration.variant, Variant::Hearts);
+        assert!(declaration.announcement.hand && !declaration.announcement.schneider);
+        assert!(!agent.is_closed());
+    }
+
+    #[test]
+    fn human_agent_closed_input() {
+        let hand = BitCards(0b111 << 10);
+        let mut output = Vec::new();
+        let mut agent = HumanAgent::new(Cursor::new("y\nyes\n"), &mut output);
+        assert!(agent.bid(hand, Position::Vorhand, 18));
+        assert!(agent.pick_up_skat(hand, Position::Vorhand, 18));
+        assert!(!agent.is_closed());
+        //every later question gets the first answer without asking
+        assert!(!agent.hold(hand, Position::Vorhand, 20));
+        assert!(agent.is_closed());
+        assert_eq!(agent.discard(hand, Position::Vorhand, 18).0, 0b110 << 10);
+        let declaration = agent.declare(hand, Position::Vorhand, true, 18);
+        assert_eq!(declaration.variant, Variant::Grand);
+        assert!(!declaration.announcement.schneider);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("[y/n]").count(), 3);
+        assert_eq!(output.matches("Hand:").count(), 3);
+        assert!(!output.contains("Game:"));
+    }
+
+    #[test]
+    fn human_agent_output() {
+        //Hearts Ass, Hearts 10 and Kreuz 7 in a hearts game
+        let hand = BitCard(1 << 13) | BitCard(1 << 12) | BitCard(1 << 21);
+        let mut view = PlayerView::new(Player::Two, Player::One, Player::One, Variant::Hearts, hand);
+        view.play(BitCard(1 << 20)).unwrap();
+        let mut output = Vec::new();
+        let mut agent = HumanAgent::new(Cursor::new("0\n"), &mut output);
+        //Two has no Piqus, so every card is legal
+        let card = agent.play_card(&view, hand);
+        assert_eq!(card, BitCard(1 << 13));
+        view.play(card).unwrap();
+        view.play(BitCard(1 << 19)).unwrap();
+        agent.trick_finished(&view, &Trick { leader: Player::One, cards: [BitCard(1 << 20), card, BitCard(1 << 19)], winner: Player::Two });
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("One plays Hearts"));
+        assert!(output.contains("Table: One: Ass of Piqus"));
+        assert!(output.contains("Hand: Ass of Hearts, 10 of Hearts, 7 of Kreuz"));
+        assert!(output.contains("Trick 1: One: Ass of Piqus, Two: Ass of Hearts, Three: 10 of Piqus -> Two takes 32 points"));
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use crate::contract::{Contract, ContractAdvisor, CONTRACTS};
+use crate::scoring::get_base_value;
+use crate::solver::bitboard::BitCards;
+use crate::solver::{Player, Variant};
+
+///Seats of the auction, Vorhand plays the first card of the game
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Position {
+    Vorhand,
+    Mittelhand,
+    Hinterhand,
+}
+
+impl Position {
+    pub fn get_player(&self, vorhand: Player) -> Player {
+        match self {
+            Position::Vorhand => vorhand,
+            Position::Mittelhand => vorhand.get_next_player(),
+            Position::Hinterhand => vorhand.get_previous_player(),
+        }
+    }
+
+    ///Seat of a player if player one is Vorhand, used to solve hands without the real seats
+    fn get_relative_player(&self) -> Player {
+        self.get_player(Player::One)
+    }
+}
+
+///Every game value which can be reached, in ascending order
+pub fn get_bid_ladder() -> Vec<u16> {
+    //matadors, game, hand, schneider, announced, schwarz, announced and ouvert
+    let mut ladder: Vec<u16> = [Variant::Clubs, Variant::Spades, Variant::Hearts, Variant::Diamonds].iter()
+        .filter_map(get_base_value)
+        .flat_map(|base_value| (2..=18).map(move |multiplier| multiplier * base_value))
+        .chain(get_base_value(&Variant::Grand).into_iter().flat_map(|base_value| (2..=11).map(move |multiplier| multiplier * base_value)))
+        .chain([Variant::Null, Variant::NullHand, Variant::NullOuvert, Variant::NullOuvertHand].iter().filter_map(get_base_value))
+        .filter(|value| *value >= 18)
+        .collect();
+    ladder.sort_unstable();
+    ladder.dedup();
+    ladder
+}
+
+///The smallest legal bid above the given one, 18 if nothing was bid yet
+pub fn get_next_bid(bid: Option<u16>) -> Option<u16> {
+    let ladder = get_bid_ladder();
+    match bid {
+        None => ladder.first().copied(),
+        Some(bid) => ladder.into_iter().find(|value| *value > bid),
+    }
+}
+
+///
+/// Decisions of one player in the auction. The bidding player says the next value or passes,
+/// the holding player holds it ("ja") or passes.
+pub trait BiddingStrategy {
+    fn bid(&mut self, hand: BitCards, position: Position, bid: u16) -> bool;
+    fn hold(&mut self, hand: BitCards, position: Position, bid: u16) -> bool;
+}
+
+///Bids and holds every value up to a fixed limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitStrategy {
+    pub max_bid: u16,
+}
+
+impl BiddingStrategy for LimitStrategy {
+    fn bid(&mut self, _hand: BitCards, _position: Position, bid: u16) -> bool {
+        bid <= self.max_bid
+    }
+
+    fn hold(&mut self, _hand: BitCards, _position: Position, bid: u16) -> bool {
+        bid <= self.max_bid
+    }
+}
+
+///
+/// Estimates the highest safe bid of a hand with the contract advisor on sampled deals:
+/// the largest bid a contract reaches which is won with at least the given probability.
+pub struct SolverStrategy {
+    samples: usize,
+    seed: u64,
+    min_win_probability: f64,
+    contracts: Vec<Contract>,
+    //limits of the hands already evaluated
+    limits: HashMap<(u32, Position), u16>,
+}
+
+impl SolverStrategy {
+    pub fn new(samples: usize, seed: u64) -> SolverStrategy {
+        SolverStrategy {
+            samples,
+            seed,
+            min_win_probability: 0.6,
+            contracts: CONTRACTS.to_vec(),
+            limits: HashMap::new(),
+        }
+    }
+
+    pub fn with_min_win_probability(mut self, min_win_probability: f64) -> SolverStrategy {
+        self.min_win_probability = min_win_probability;
+        self
+    }
+
+    ///Only the given contracts are considered, see ContractAdvisor::with_contracts
+    pub fn with_contracts(mut self, contracts: &[Contract]) -> SolverStrategy {
+        assert!(contracts.iter().all(Contract::is_valid), "Ramsch is no contract");
+        self.contracts = contracts.to_vec();
+        self
+    }
+
+    ///Highest bid for the hand, none if no contract is safe enough
+    pub fn get_max_bid(&mut self, hand: BitCards, position: Position) -> Option<u16> {
+        let limit = match self.limits.get(&(hand.0, position)) {
+            Some(limit) => *limit,
+            None => {
+                let advisor = ContractAdvisor::new(self.samples, self.seed).with_contracts(&self.contracts);
+                let advices = advisor.advise(hand, None, position.get_relative_player(), Player::One);
+                let ladder = get_bid_ladder();
+                let limit = advices.iter()
+                    .filter(|advice| advice.win_probability >= self.min_win_probability)
+                    .filter_map(|advice| ladder.iter().rev().find(|value| **value as f64 <= advice.expected_game_value))
+                    .max()
+                    .copied()
+                    .unwrap_or(0);
+                self.limits.insert((hand.0, position), limit);
+                limit
+            }
+        };
+        if limit == 0 {
+            None
+        } else {
+            Some(limit)
+        }
+    }
+}
+
+impl BiddingStrategy for SolverStrategy {
+    fn bid(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        self.get_max_bid(hand, position).is_some_and(|max_bid| bid <= max_bid)
+    }
+
+    fn hold(&mut self, hand: BitCards, position: Position, bid: u16) -> bool {
+        self.get_max_bid(hand, position).is_some_and(|max_bid| bid <= max_bid)
+    }
+}
+
+///Result of the auction, no declarer if every player passed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuctionResult {
+    pub declarer: Option<Player>,
+    pub bid: u16,
+}
+
+///
+/// Runs the auction: Mittelhand bids to Vorhand, then Hinterhand bids to the remaining player.
+/// If nobody bid, the remaining player can still play for 18.
+/// hands and strategies are indexed by the players.
+pub fn run_auction(hands: [BitCards; 3], vorhand: Player, strategies: &mut [&mut dyn BiddingStrategy; 3]) -> AuctionResult {
+    let mut ask = |position: Position, bid: u16, holding: bool| {
+        let player = position.get_player(vorhand) as usize;
+        if holding {
+            strategies[player].hold(hands[player], position, bid)
+        } else {
+            strategies[player].bid(hands[player], position, bid)
+        }
+    };
+    let (holder, bid) = run_duel(Position::Mittelhand, Position::Vorhand, None, &mut ask);
+    let (holder, bid) = run_duel(Position::Hinterhand, holder, bid, &mut ask);
+    match bid {
+        Some(bid) => AuctionResult {
+            declarer: Some(holder.get_player(vorhand)),
+            bid,
+        },
+        None if ask(holder, 18, false) => AuctionResult {
+            declarer: Some(holder.get_player(vorhand)),
+            bid: 18,
+        },
+        None => AuctionResult {
+            declarer: None,
+            bid: 0,
+        },
+    }
+}
+
+///The bidder says values until one of both passes, returns the remaining player and the last bid
+fn run_duel(bidder: Position, holder: Position, mut bid: Option<u16>, ask: &mut impl FnMut(Position, u16, bool) -> bool) -> (Position, Option<u16>) {
+    while let Some(next) = get_next_bid(bid) {
+        if !ask(bidder, next, false) {
+            return (holder, bid);
+        }
+        if !ask(holder, next, true) {
+            return (bidder, Some(next));
+        }
+        bid = Some(next);
+    }
+    (holder, bid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bidding::{AuctionResult, BiddingStrategy, get_bid_ladder, get_next_bid, LimitStrategy, Position, run_auction, SolverStrategy};
+    use crate::dataset::read_dataset;
+    use crate::solver::bitboard::BitCards;
+    use crate::solver::Player;
+
+    #[test]
+    fn bid_ladder() {
+        let ladder = get_bid_ladder();
+        assert_eq!(ladder[..12], [18, 20, 22, 23, 24, 27, 30, 33, 35, 36, 40, 44]);
+        assert_eq!(ladder.last(), Some(&264));
+        assert!(ladder.contains(&59) && ladder.contains(&46));
+        assert_eq!(get_next_bid(None), Some(18));
+        assert_eq!(get_next_bid(Some(24)), Some(27));
+        assert_eq!(get_next_bid(Some(264)), None);
+    }
+
+    fn run_limits(limits: [u16; 3], vorhand: Player) -> AuctionResult {
+        let mut strategies = limits.map(|max_bid| LimitStrategy { max_bid });
+        let [first, second, third] = &mut strategies;
+        run_auction([BitCards(0); 3], vorhand, &mut [first, second, third])
+    }
+
+    #[test]
+    fn auction() {
+        //Mittelhand gives up at 27, Hinterhand passes at once
+        assert_eq!(run_limits([30, 24, 0], Player::One), AuctionResult { declarer: Some(Player::One), bid: 24 });
+        //Vorhand passes at 27 against Mittelhand, Hinterhand goes up to 33
+        assert_eq!(run_limits([24, 30, 33], Player::One), AuctionResult { declarer: Some(Player::Three), bid: 33 });
+        //the seats move with Vorhand
+        assert_eq!(run_limits([33, 30, 24], Player::Two), AuctionResult { declarer: Some(Player::One), bid: 33 });
+        //nobody bids, Vorhand plays for 18
+        assert_eq!(run_limits([18, 0, 0], Player::One), AuctionResult { declarer: Some(Player::One), bid: 18 });
+        assert_eq!(run_limits([0, 0, 20], Player::One), AuctionResult { declarer: Some(Player::Three), bid: 18 });
+        assert_eq!(run_limits([0, 0, 0], Player::Three), AuctionResult { declarer: None, bid: 0 });
+    }
+
+    #[test]
+    fn solver_strategy() {
+        let p1 = read_dataset("data/five_cards.txt").unwrap()[0].player_cards.0;
+        let mut strategy = SolverStrategy::new(2, 4).with_min_win_probability(0.0);
+        //every contract is good enough without a minimal probability
+        let max_bid = strategy.get_max_bid(p1, Position::Vorhand);
+        assert!(max_bid.is_some_and(|max_bid| max_bid >= 18));
+        assert!(strategy.bid(p1, Position::Vorhand, max_bid.unwrap()));
+        assert!(!strategy.hold(p1, Position::Vorhand, max_bid.unwrap() + 1));
+        let mut strategy = SolverStrategy::new(2, 4).with_min_win_probability(1.1);
+        assert_eq!(strategy.get_max_bid(p1, Position::Vorhand), None);
+    }
+}
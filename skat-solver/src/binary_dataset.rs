@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::dataset::{DatasetError, DatasetReader, DatasetWriter, SolvedPosition};
+use crate::solver::bitboard::BitCards;
+use crate::solver::{Player, Variant};
+
+///Start of every binary file
+pub const MAGIC: [u8; 4] = *b"SKDS";
+pub const VERSION: u16 = 1;
+pub const HEADER_SIZE: usize = 8;
+pub const RECORD_SIZE: usize = 24;
+
+const CHECKED_SIZE: usize = RECORD_SIZE - 4;
+
+///
+/// Binary files of solved positions. The header is the magic number, the version and the record size,
+/// followed by records of the three hands and the skat as little endian bit masks, the variant,
+/// the declarer, the leader, the result and the CRC-32 of the first twenty bytes.
+/// The header is written on creation. Only positions without cards on the table can be stored,
+/// the solver is not stored.
+pub struct BinaryWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<BinaryWriter<W>> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(RECORD_SIZE as u16).to_le_bytes())?;
+        Ok(BinaryWriter {
+            writer,
+        })
+    }
+
+    pub fn write(&mut self, position: &SolvedPosition) -> io::Result<()> {
+        self.writer.write_all(&encode_record(position)?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+///Reads the records one after another, a record which was cut off is an error
+pub struct BinaryReader<R: Read> {
+    reader: R,
+    //number of the next record, starting with one
+    record: usize,
+}
+
+impl<R: Read> BinaryReader<R> {
+    pub fn new(mut reader: R) -> Result<BinaryReader<R>, DatasetError> {
+        let mut header = [0; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(DatasetError::Header(format!("{:?}", &header[..4])));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version > VERSION {
+            return Err(DatasetError::Version(version as u32));
+        }
+        let record_size = u16::from_le_bytes([header[6], header[7]]);
+        if record_size as usize != RECORD_SIZE {
+            return Err(DatasetError::Header(format!("record size {record_size}")));
+        }
+        Ok(BinaryReader {
+            reader,
+            record: 1,
+        })
+    }
+
+    fn read_record(&mut self) -> Result<Option<[u8; RECORD_SIZE]>, DatasetError> {
+        let mut record = [0; RECORD_SIZE];
+        let mut read = 0;
+        while read < RECORD_SIZE {
+            match self.reader.read(&mut record[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(amount) => read += amount,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(Some(record))
+    }
+}
+
+impl<R: Read> Iterator for BinaryReader<R> {
+    type Item = Result<SolvedPosition, DatasetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.read_record().transpose()? {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error)),
+        };
+        let position = decode_record(&record, self.record);
+        self.record += 1;
+        Some(position)
+    }
+}
+
+pub fn encode_record(position: &SolvedPosition) -> io::Result<[u8; RECORD_SIZE]> {
+    if !position.table.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "records have no cards on the table"));
+    }
+    let (p1, p2, p3) = position.player_cards;
+    let mut record = [0; RECORD_SIZE];
+    for (index, cards) in [p1, p2, p3, position.skat].into_iter().enumerate() {
+        record[4 * index..4 * index + 4].copy_from_slice(&cards.0.to_le_bytes());
+    }
+    record[16] = position.variant as u8;
+    record[17] = position.declarer as u8;
+    record[18] = position.current_player as u8;
+    record[19] = position.result;
+    let checksum = crc32(&record[..CHECKED_SIZE]);
+    record[CHECKED_SIZE..].copy_from_slice(&checksum.to_le_bytes());
+    Ok(record)
+}
+
+///The position of a record, the number of the record is used for errors
+pub fn decode_record(record: &[u8; RECORD_SIZE], number: usize) -> Result<SolvedPosition, DatasetError> {
+    let checksum = u32::from_le_bytes(record[CHECKED_SIZE..].try_into().unwrap());
+    if crc32(&record[..CHECKED_SIZE]) != checksum {
+        return Err(DatasetError::Checksum { record: number });
+    }
+    let cards = |index: usize| BitCards(u32::from_le_bytes(record[4 * index..4 * index + 4].try_into().unwrap()));
+    let player = |value: u8, field: &'static str| match value {
+        0..=2 => Ok(Player::from(value)),
+        _ => Err(DatasetError::InvalidRecord { record: number, field }),
+    };
+    if record[16] > Variant::Ramsch as u8 {
+        return Err(DatasetError::InvalidRecord { record: number, field: "variant" });
+    }
+    let mut position = SolvedPosition::new((cards(0), cards(1), cards(2)), cards(3), player(record[18], "leader")?, Variant::from(record[16]), record[19]);
+    position.declarer = player(record[17], "declarer")?;
+    Ok(position)
+}
+
+///CRC-32 with the polynomial of zip and png
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+///Converts a versioned or legacy text file into records, returns the amount of records
+pub fn text_to_binary<R: BufRead, W: Write>(reader: R, writer: W) -> Result<usize, DatasetError> {
+    let mut writer = BinaryWriter::new(writer)?;
+    let mut records = 0;
+    for position in DatasetReader::new(reader)? {
+        writer.write(&position?)?;
+        records += 1;
+    }
+    writer.into_inner().flush()?;
+    Ok(records)
+}
+
+///Writes every record with the text writer, returns the amount of records
+pub fn binary_to_text<R: Read, W: Write>(reader: R, writer: &mut DatasetWriter<W>) -> Result<usize, DatasetError> {
+    let mut records = 0;
+    for position in BinaryReader::new(reader)? {
+        writer.write(&position?)?;
+        records += 1;
+    }
+    Ok(records)
+}
+
+pub fn read_binary_dataset<P: AsRef<Path>>(path: P) -> Result<Vec<SolvedPosition>, DatasetError> {
+    BinaryReader::new(BufReader::new(File::open(path)?))?.collect()
+}
+
+pub fn write_binary_dataset<P: AsRef<Path>>(path: P, positions: &[SolvedPosition]) -> io::Result<()> {
+    let mut writer = BinaryWriter::new(BufWriter::new(File::create(path)?))?;
+    for position in positions {
+        writer.write(position)?;
+    }
+    writer.into_inner().flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+    use crate::binary_dataset::{binary_to_text, BinaryReader, BinaryWriter, crc32, HEADER_SIZE, RECORD_SIZE, text_to_binary};
+    use crate::dataset::{DatasetError, DatasetWriter, read_dataset, SolvedPosition};
+    use crate::solver::bitboard::{BitCards, KREUZ_ASS};
+    use crate::solver::{Player, Variant};
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn legacy_round_trip() {
+        for entry in fs::read_dir("data").unwrap() {
+            let path = entry.unwrap().path();
+            let input = fs::read_to_string(&path).unwrap();
+            let mut binary = Vec::new();
+            let records = text_to_binary(input.as_bytes(), &mut binary).unwrap();
+            assert_eq!(records, input.lines().count());
+            assert_eq!(binary.len(), HEADER_SIZE + records * RECORD_SIZE);
+            let positions: Vec<SolvedPosition> = BinaryReader::new(binary.as_slice()).unwrap().collect::<Result<_, _>>().unwrap();
+            assert_eq!(positions, read_dataset(&path).unwrap());
+            let mut writer = DatasetWriter::legacy(Vec::new());
+            assert_eq!(binary_to_text(binary.as_slice(), &mut writer).unwrap(), records);
+            let output = String::from_utf8(writer.into_inner()).unwrap();
+            assert!(output.lines().eq(input.lines()), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_records() {
+        let mut position = SolvedPosition::new((BitCards(1), BitCards(2), BitCards(4)), BitCards(8), Player::Three, Variant::Null, 1);
+        position.declarer = Player::Two;
+        let mut writer = BinaryWriter::new(Vec::new()).unwrap();
+        writer.write(&position).unwrap();
+        writer.write(&position).unwrap();
+        let binary = writer.into_inner();
+        let positions: Vec<SolvedPosition> = BinaryReader::new(binary.as_slice()).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(positions, [position.clone(), position.clone()]);
+        //a changed bit in the second record
+        let mut changed = binary.clone();
+        changed[HEADER_SIZE + RECORD_SIZE + 3] ^= 1;
+        let mut reader = BinaryReader::new(Cursor::new(changed)).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next().unwrap(), Err(DatasetError::Checksum { record: 2 })));
+        //a record which was cut off
+        let results: Vec<_> = BinaryReader::new(&binary[..binary.len() - 1]).unwrap().collect();
+        assert!(matches!(results[1], Err(DatasetError::Io(_))));
+        assert!(matches!(BinaryReader::new(&b"SKAT\x01\x00\x18\x00"[..]), Err(DatasetError::Header(_))));
+        assert!(matches!(BinaryReader::new(&b"SKDS\x02\x00\x18\x00"[..]), Err(DatasetError::Version(2))));
+        position.table = vec![KREUZ_ASS];
+        assert!(BinaryWriter::new(Vec::new()).unwrap().write(&position).is_err());
+    }
+}
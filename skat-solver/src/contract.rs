@@ -0,0 +1,273 @@
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use crate::discard::best_discard;
+use crate::scoring::{Announcement, calculate_game_value, GameResult, Outcome};
+use crate::solver::bitboard::{BitCard, BitCards};
+use crate::solver::bitstates::{BitGlobal, BitLocal};
+use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+use crate::solver::synchronus::threshold::Target;
+use crate::solver::{GlobalState, Player, Variant};
+
+///A game the declarer can announce, for null games hand is part of the variant
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contract {
+    pub variant: Variant,
+    pub hand: bool,
+}
+
+impl Contract {
+    ///Ramsch is played when nobody declares, so it is no contract
+    pub fn is_valid(&self) -> bool {
+        self.variant != Variant::Ramsch
+    }
+}
+
+const fn contract(variant: Variant, hand: bool) -> Contract {
+    Contract { variant, hand }
+}
+
+///Every contract without schneider, schwarz or ouvert announcements
+pub const CONTRACTS: [Contract; 14] = [
+    contract(Variant::Grand, false),
+    contract(Variant::Clubs, false),
+    contract(Variant::Spades, false),
+    contract(Variant::Hearts, false),
+    contract(Variant::Diamonds, false),
+    contract(Variant::Null, false),
+    contract(Variant::NullOuvert, false),
+    contract(Variant::Grand, true),
+    contract(Variant::Clubs, true),
+    contract(Variant::Spades, true),
+    contract(Variant::Hearts, true),
+    contract(Variant::Diamonds, true),
+    contract(Variant::NullHand, true),
+    contract(Variant::NullOuvertHand, true),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContractAdvice {
+    pub contract: Contract,
+    ///average score of the declarer, lost games count twice negative
+    pub expected_score: f64,
+    ///average game value, won or lost
+    pub expected_game_value: f64,
+    pub win_probability: f64,
+}
+
+///
+/// Solves every contract for the hand of the declarer and turns the results into game values.
+/// Without a skat pickup the skat stays closed, with it the best discard of every deal is played.
+pub struct ContractAdvisor {
+    samples: usize,
+    seed: u64,
+    bid: u16,
+    contracts: Vec<Contract>,
+}
+
+impl ContractAdvisor {
+    pub fn new(samples: usize, seed: u64) -> ContractAdvisor {
+        ContractAdvisor {
+            samples,
+            seed,
+            bid: 0,
+            contracts: CONTRACTS.to_vec(),
+        }
+    }
+
+    ///Games with a smaller value than the bid are lost as overbid
+    pub fn with_bid(mut self, bid: u16) -> ContractAdvisor {
+        self.bid = bid;
+        self
+    }
+
+    ///Only the given contracts are solved, picking up the skat is expensive as every discard is tried
+    pub fn with_contracts(mut self, contracts: &[Contract]) -> ContractAdvisor {
+        assert!(contracts.iter().all(Contract::is_valid), "Ramsch is no contract");
+        self.contracts = contracts.to_vec();
+        self
+    }
+
+    ///All hands and the skat are known, the best contract comes first
+    pub fn advise_double_dummy(&self, player_cards: (BitCards, BitCards, BitCards), skat: BitCards, declarer: Player, starting_player: Player) -> Vec<ContractAdvice> {
+        let results = self.evaluate_deal([player_cards.0, player_cards.1, player_cards.2], skat, declarer, starting_player);
+        aggregate(&self.contracts, &[results])
+    }
+
+    ///
+    /// Only the hand of the declarer and maybe the skat are known, the other cards are dealt randomly.
+    /// The opponents get as many cards as the declarer, cards which are left are not part of the game.
+    pub fn advise(&self, hand: BitCards, skat: Option<BitCards>, declarer: Player, starting_player: Player) -> Vec<ContractAdvice> {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let known = hand | skat.unwrap_or(BitCards(0));
+        let mut hidden: Vec<BitCard> = BitCards(!known.0).collect();
+        let hand_size = hand.0.count_ones() as usize;
+        let mut results = Vec::new();
+        for _ in 0..self.samples {
+            hidden.shuffle(&mut rng);
+            let mut cards = hidden.iter();
+            let first: BitCards = cards.by_ref().take(hand_size).fold(BitCards(0), |cards, card| cards | *card);
+            let second: BitCards = cards.by_ref().take(hand_size).fold(BitCards(0), |cards, card| cards | *card);
+            let skat = skat.unwrap_or_else(|| cards.take(2).fold(BitCards(0), |cards, card| cards | *card));
+            let mut hands = [BitCards(0); 3];
+            hands[declarer as usize] = hand;
+            hands[declarer.get_next_player() as usize] = first;
+            hands[declarer.get_previous_player() as usize] = second;
+            results.push(self.evaluate_deal(hands, skat, declarer, starting_player));
+        }
+        aggregate(&self.contracts, &results)
+    }
+
+    fn evaluate_deal(&self, hands: [BitCards; 3], skat: BitCards, declarer: Player, starting_player: Player) -> Vec<(Contract, GameResult)> {
+        self.contracts.iter().map(|contract| {
+            let variant = contract.variant;
+            let (global_state, value) = if contract.hand {
+                let value = solve_hand(hands, skat, declarer, starting_player, variant);
+                (GlobalState::new((hands[0], hands[1], hands[2]), skat, declarer, variant), value)
+            } else {
+                let mut picked_up = hands;
+                picked_up[declarer as usize] = hands[declarer as usize] | skat;
+                let discard = best_discard((picked_up[0], picked_up[1], picked_up[2]), declarer, starting_player, variant)[0];
+                picked_up[declarer as usize] = BitCards(picked_up[declarer as usize].0 & !discard.skat.0);
+                (GlobalState::new((picked_up[0], picked_up[1], picked_up[2]), discard.skat, declarer, variant), discard.value as u8)
+            };
+            let outcome = if variant.is_null() {
+                Outcome::from_null(value)
+            } else {
+                let tricks = hands[declarer as usize].0.count_ones() as u8;
+                Outcome::new(value, solve_tricks(&global_state, starting_player, value), tricks)
+            };
+            let announcement = Announcement { hand: contract.hand, ..Default::default() };
+            (*contract, calculate_game_value(&global_state, &announcement, &outcome, self.bid).expect("Ramsch is no contract"))
+        }).collect()
+    }
+}
+
+///Card points of the declarer including the closed skat, tricks in null games
+fn solve_hand(hands: [BitCards; 3], skat: BitCards, declarer: Player, starting_player: Player, variant: Variant) -> u8 {
+    let global_state = BitGlobal::new(hands[0], hands[1], hands[2], declarer, variant);
+    let local_state = BitLocal::new((hands[0] | hands[1] | hands[2]).0, starting_player, &global_state);
+    let mut solver = MoreEnhancedSolver::new(global_state);
+    //the global state only knows the skat if all other cards are in the hands
+    let skat_points = if variant.is_null() { 0 } else { skat.get_cards_points() };
+    solver.solve(local_state) - solver.global_state.skat_points + skat_points
+}
+
+///
+/// Tricks of the declarer as far as they decide Schwarz, as a trick can be worth zero points.
+/// All tricks if the declarer gets every point and can take every trick, none if the declarer only
+/// gets the skat and the opponents can take every trick, one trick otherwise.
+fn solve_tricks(global_state: &GlobalState, starting_player: Player, declarer_points: u8) -> u8 {
+    let (p1, p2, p3) = global_state.player_cards;
+    let all_cards = p1 | p2 | p3;
+    let tricks = p1.0.count_ones() as u8;
+    let reaches = |target: u8| {
+        let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, global_state.alone_player, global_state.variant));
+        solver.solve_target(BitLocal::new(all_cards.0, starting_player, &solver.global_state), Target::Tricks(target)).reached
+    };
+    if declarer_points == (all_cards | global_state.skat).get_cards_points() && reaches(tricks) {
+        tricks
+    } else if declarer_points == global_state.skat.get_cards_points() && !reaches(1) {
+        0
+    } else {
+        1
+    }
+}
+
+///Averages the results of every deal, the contract with the best expected score comes first
+fn aggregate(contracts: &[Contract], results: &[Vec<(Contract, GameResult)>]) -> Vec<ContractAdvice> {
+    let amount = results.len() as f64;
+    let mut advices: Vec<ContractAdvice> = contracts.iter().enumerate().map(|(i, contract)| {
+        let mut advice = ContractAdvice {
+            contract: *contract,
+            expected_score: 0.0,
+            expected_game_value: 0.0,
+            win_probability: 0.0,
+        };
+        for deal in results {
+            let result = deal[i].1;
+            advice.expected_score += result.score as f64 / amount;
+            advice.expected_game_value += result.game_value as f64 / amount;
+            advice.win_probability += result.won as u8 as f64 / amount;
+        }
+        advice
+    }).collect();
+    advices.sort_by(|a, b| b.expected_score.total_cmp(&a.expected_score));
+    advices
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::read_dataset;
+    use crate::contract::{Contract, ContractAdvisor, CONTRACTS, solve_tricks};
+    use crate::scoring::{Announcement, calculate_game_value, Outcome};
+    use crate::solver::bitboard::{BitCards, HEARTS_ASS, HEARTS_EIGHT, HEARTS_NINE, HEARTS_SEVEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_SEVEN, KREUZ_TEN};
+    use crate::solver::bitstates::{BitGlobal, BitLocal};
+    use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use crate::solver::synchronus::threshold::Target;
+    use crate::solver::{GlobalState, Player, Variant};
+
+    #[test]
+    fn schwarz_tricks() {
+        let tricks = |p1, p2, p3, points| solve_tricks(&GlobalState::new((p1, p2, p3), BitCards(0), Player::One, Variant::Grand), Player::One, points);
+        //all points but the last trick is worth nothing
+        assert_eq!(tricks(KREUZ_ASS | HEARTS_SEVEN, KREUZ_SEVEN | HEARTS_EIGHT, KREUZ_EIGHT | HEARTS_NINE, 11), 1);
+        assert_eq!(tricks(KREUZ_ASS | HEARTS_ASS, KREUZ_SEVEN | HEARTS_EIGHT, KREUZ_EIGHT | HEARTS_NINE, 22), 2);
+        //no points but a trick worth nothing
+        assert_eq!(tricks(HEARTS_NINE | KREUZ_SEVEN, HEARTS_SEVEN | KREUZ_ASS, HEARTS_EIGHT | KREUZ_TEN, 0), 1);
+        assert_eq!(tricks(HEARTS_SEVEN | KREUZ_SEVEN, HEARTS_ASS | KREUZ_ASS, HEARTS_EIGHT | KREUZ_EIGHT, 0), 0);
+    }
+
+    #[test]
+    fn contract_double_dummy() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(10) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let score = position.result;
+            let mut out = BitCards(!(p1 | p2 | p3).0);
+            let skat = out.next().unwrap() | out.next().unwrap();
+            let advices = ContractAdvisor::new(1, 0).with_bid(18).advise_double_dummy((p1, p2, p3), skat, Player::One, current_player);
+            assert_eq!(advices.len(), 14);
+            assert!(advices.windows(2).all(|pair| pair[0].expected_score >= pair[1].expected_score));
+            assert!(advices.iter().all(|advice| advice.win_probability == 0.0 || advice.win_probability == 1.0));
+            //the hand game of the data line has its solved score plus the closed skat
+            let hand = !variant.is_null() || variant == Variant::NullHand || variant == Variant::NullOuvertHand;
+            let Some(advice) = advices.iter().find(|advice| advice.contract == Contract { variant, hand }) else {
+                continue;
+            };
+            let points = if variant.is_null() { score } else { score + skat.get_cards_points() };
+            let outcome = if variant.is_null() {
+                Outcome::from_null(points)
+            } else {
+                //schwarz needs every or no trick, not only every or no point
+                let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, Player::One, variant));
+                let local_state = BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state);
+                let all_tricks = points == (p1 | p2 | p3 | skat).get_cards_points() && solver.solve_target(local_state, Target::Tricks(5)).reached;
+                let no_trick = points == skat.get_cards_points() && !solver.solve_target(local_state, Target::Tricks(1)).reached;
+                Outcome::new(points, if all_tricks { 5 } else if no_trick { 0 } else { 1 }, 5)
+            };
+            let global_state = GlobalState::new((p1, p2, p3), skat, Player::One, variant);
+            let result = calculate_game_value(&global_state, &Announcement { hand, ..Default::default() }, &outcome, 18).unwrap();
+            assert_eq!(advice.expected_score, result.score as f64);
+            assert_eq!(advice.expected_game_value, result.game_value as f64);
+        }
+    }
+
+    #[test]
+    fn contract_sampled() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(3) {
+            let p1 = position.player_cards.0;
+            let advisor = ContractAdvisor::new(3, 8);
+            let advices = advisor.advise(p1, None, Player::One, Player::One);
+            assert_eq!(advices.len(), 14);
+            assert!(advices.iter().all(|advice| (0.0..=1.0).contains(&advice.win_probability)));
+            assert_eq!(advices, advisor.advise(p1, None, Player::One, Player::One));
+            //hand contracts alone give the same advices
+            let hand_contracts: Vec<Contract> = CONTRACTS.iter().filter(|contract| contract.hand).copied().collect();
+            let hand_advices = ContractAdvisor::new(3, 8).with_contracts(&hand_contracts).advise(p1, None, Player::One, Player::One);
+            assert_eq!(hand_advices.len(), 7);
+            assert!(hand_advices.iter().all(|advice| advices.contains(advice)));
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::path::Path;
+use crate::solver::bitboard::{BitCard, BitCards};
+use crate::solver::synchronus::local_state::LState;
+use crate::solver::{Deal, GlobalState, Player, TrickError, Variant};
+
+///Version of the files written by the dataset writer
+pub const VERSION: u32 = 1;
+
+const HEADER: &str = "#skat-dataset";
+
+//columns of the current version in the order they are written
+const PLAYER_ONE: usize = 0;
+const PLAYER_TWO: usize = 1;
+const PLAYER_THREE: usize = 2;
+const SKAT: usize = 3;
+const DECLARER: usize = 4;
+const TABLE: usize = 5;
+const CURRENT_PLAYER: usize = 6;
+const VARIANT: usize = 7;
+const RESULT: usize = 8;
+const SOLVER: usize = 9;
+const FIELDS: [&str; 10] = ["player_one", "player_two", "player_three", "skat", "declarer", "table", "current_player", "variant", "result", "solver"];
+//the skat, the declarer, the table and the solver can be left out
+const REQUIRED_FIELDS: [usize; 6] = [PLAYER_ONE, PLAYER_TWO, PLAYER_THREE, CURRENT_PLAYER, VARIANT, RESULT];
+const LEGACY_FIELDS: [usize; 8] = [PLAYER_ONE, PLAYER_TWO, PLAYER_THREE, SKAT, CURRENT_PLAYER, VARIANT, RESULT, DECLARER];
+
+///A position with the score of the declarer found by a solver
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolvedPosition {
+    ///hands at the start of the trick, including the cards on the table
+    pub player_cards: (BitCards, BitCards, BitCards),
+    pub skat: BitCards,
+    pub declarer: Player,
+    ///cards of the open trick in the order they were played
+    pub table: Vec<BitCard>,
+    pub current_player: Player,
+    pub variant: Variant,
+    ///card points of the declarer including the skat, in null games the tricks of the declarer
+    pub result: u8,
+    pub solver: Option<String>,
+}
+
+impl SolvedPosition {
+    pub fn new(player_cards: (BitCards, BitCards, BitCards), skat: BitCards, current_player: Player, variant: Variant, result: u8) -> SolvedPosition {
+        SolvedPosition {
+            player_cards,
+            skat,
+            declarer: Player::One,
+            table: Vec::new(),
+            current_player,
+            variant,
+            result,
+            solver: None,
+        }
+    }
+
+    ///Cards still in the hands of the players
+    pub fn get_remaining_cards(&self) -> BitCards {
+        let table = self.table.iter().fold(BitCards(0), |cards, card| cards | *card);
+        BitCards((self.player_cards.0 | self.player_cards.1 | self.player_cards.2).0 & !table.0)
+    }
+
+    pub fn get_global_state(&self) -> GlobalState {
+        GlobalState::new(self.player_cards, self.skat, self.declarer, self.variant)
+    }
+
+    ///The state of the position including the open trick
+    pub fn get_local_state(&self, global_state: &GlobalState) -> Result<LState, TrickError> {
+        LState::with_trick(self.get_remaining_cards(), self.current_player, &self.table, global_state)
+    }
+
+    ///The deal of the position, a deal starts with an empty table so positions with cards on the table have none
+    pub fn get_deal(&self) -> Option<Deal> {
+        if !self.table.is_empty() {
+            return None;
+        }
+        Some(Deal::new(self.player_cards, self.skat, self.current_player, self.variant).with_declarer(self.declarer))
+    }
+}
+
+#[derive(Debug)]
+pub enum DatasetError {
+    Io(io::Error),
+    ///the file was written by a newer version
+    Version(u32),
+    Header(String),
+    MissingField { line: usize, field: &'static str },
+    InvalidField { line: usize, field: &'static str, value: String },
+    ///the record of a binary file was changed after it was written
+    Checksum { record: usize },
+    InvalidRecord { record: usize, field: &'static str },
+}
+
+impl Display for DatasetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io(error) => write!(f, "{error}"),
+            DatasetError::Version(version) => write!(f, "version {version} is newer than {VERSION}"),
+            DatasetError::Header(header) => write!(f, "invalid header {header}"),
+            DatasetError::MissingField { line, field } => write!(f, "line {line}: {field} is missing"),
+            DatasetError::InvalidField { line, field, value } => write!(f, "line {line}: {value} is not a valid {field}"),
+            DatasetError::Checksum { record } => write!(f, "record {record}: wrong checksum"),
+            DatasetError::InvalidRecord { record, field } => write!(f, "record {record}: invalid {field}"),
+        }
+    }
+}
+
+impl Error for DatasetError {}
+
+impl From<io::Error> for DatasetError {
+    fn from(error: io::Error) -> Self {
+        DatasetError::Io(error)
+    }
+}
+
+///
+/// Reads the positions of a versioned or legacy file line by line. A versioned file starts with the header
+/// `#skat-dataset version=1` and a line with the names of the columns, e.g.
+/// `player_one,player_two,player_three,skat,declarer,table,current_player,variant,result,solver`.
+/// Cards are decimal bit masks, players are 0 to 2 and variants their number. The table lists the cards
+/// of the open trick in the order they were played, separated by spaces.
+/// Files without a header are legacy files `p1,p2,p3,skat,current_player,variant,result`,
+/// optionally followed by the declarer.
+pub struct DatasetReader<R: BufRead> {
+    lines: Lines<R>,
+    //the first data line of a legacy file is read to find out that there is no header
+    first_line: Option<String>,
+    line: usize,
+    version: Option<u32>,
+    //index of the column of every field
+    columns: [Option<usize>; 10],
+}
+
+impl<R: BufRead> DatasetReader<R> {
+    pub fn new(reader: R) -> Result<DatasetReader<R>, DatasetError> {
+        let mut dataset = DatasetReader {
+            lines: reader.lines(),
+            first_line: None,
+            line: 0,
+            version: None,
+            columns: [None; 10],
+        };
+        let Some(first_line) = dataset.next_line()? else {
+            return Ok(dataset);
+        };
+        let Some(header) = first_line.strip_prefix(HEADER) else {
+            for (column, field) in LEGACY_FIELDS.into_iter().enumerate() {
+                dataset.columns[field] = Some(column);
+            }
+            dataset.first_line = Some(first_line);
+            return Ok(dataset);
+        };
+        let version = header.trim().strip_prefix("version=")
+            .and_then(|version| version.parse::<u32>().ok())
+            .ok_or_else(|| DatasetError::Header(first_line.clone()))?;
+        if version > VERSION {
+            return Err(DatasetError::Version(version));
+        }
+        dataset.version = Some(version);
+        let names = dataset.next_line()?.ok_or_else(|| DatasetError::Header(first_line.clone()))?;
+        for (column, name) in names.split(',').enumerate() {
+            //unknown columns are skipped
+            if let Some(field) = FIELDS.iter().position(|field| *field == name.trim()) {
+                dataset.columns[field] = Some(column);
+            }
+        }
+        if let Some(field) = REQUIRED_FIELDS.into_iter().find(|field| dataset.columns[*field].is_none()) {
+            return Err(DatasetError::MissingField { line: dataset.line, field: FIELDS[field] });
+        }
+        Ok(dataset)
+    }
+
+    ///The version of the header, none for a legacy file
+    pub fn get_version(&self) -> Option<u32> {
+        self.version
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>, DatasetError> {
+        for line in self.lines.by_ref() {
+            self.line += 1;
+            let line = line?;
+            if !line.trim().is_empty() {
+                return Ok(Some(line));
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse_line(&self, line: &str) -> Result<SolvedPosition, DatasetError> {
+        let values: Vec<&str> = line.split(',').map(str::trim).collect();
+        let get = |field: usize| self.columns[field].and_then(|column| values.get(column)).copied().filter(|value| !value.is_empty());
+        let invalid = |field: usize, value: &str| DatasetError::InvalidField { line: self.line, field: FIELDS[field], value: value.to_string() };
+        let required = |field: usize| get(field).ok_or(DatasetError::MissingField { line: self.line, field: FIELDS[field] });
+        let cards = |field: usize| match get(field) {
+            Some(value) => value.parse::<u32>().map(BitCards).map_err(|_| invalid(field, value)),
+            None => Ok(BitCards(0)),
+        };
+        let player = |field: usize, value: &str| match value.parse::<u8>() {
+            Ok(player) if player < 3 => Ok(Player::from(player)),
+            _ => Err(invalid(field, value)),
+        };
+        let variant = required(VARIANT)?;
+        let variant = match variant.parse::<u8>() {
+            Ok(number) if number <= Variant::Ramsch as u8 => Variant::from(number),
+            _ => return Err(invalid(VARIANT, variant)),
+        };
+        let table = match get(TABLE) {
+            Some(value) => value.split_whitespace()
+                .map(|card| match card.parse::<u32>() {
+                    Ok(card) if card.count_ones() == 1 => Ok(BitCard(card)),
+                    _ => Err(invalid(TABLE, value)),
+                })
+                .collect::<Result<Vec<BitCard>, DatasetError>>()?,
+            None => Vec::new(),
+        };
+        let result = required(RESULT)?;
+        Ok(SolvedPosition {
+            player_cards: (cards(PLAYER_ONE)?, cards(PLAYER_TWO)?, cards(PLAYER_THREE)?),
+            skat: cards(SKAT)?,
+            declarer: get(DECLARER).map_or(Ok(Player::One), |value| player(DECLARER, value))?,
+            table,
+            current_player: player(CURRENT_PLAYER, required(CURRENT_PLAYER)?)?,
+            variant,
+            result: result.parse::<u8>().map_err(|_| invalid(RESULT, result))?,
+            solver: get(SOLVER).map(str::to_string),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for DatasetReader<R> {
+    type Item = Result<SolvedPosition, DatasetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.first_line.take().map(Ok).or_else(|| self.next_line().transpose())? {
+            Ok(line) => line,
+            Err(error) => return Some(Err(error)),
+        };
+        Some(self.parse_line(&line))
+    }
+}
+
+///
+/// Writes positions in the current version, the header is written on creation.
+pub struct DatasetWriter<W: Write> {
+    writer: W,
+    legacy: bool,
+}
+
+impl<W: Write> DatasetWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<DatasetWriter<W>> {
+        writeln!(writer, "{HEADER} version={VERSION}")?;
+        writeln!(writer, "{}", FIELDS.join(","))?;
+        Ok(DatasetWriter {
+            writer,
+            legacy: false,
+        })
+    }
+
+    ///
+    /// Writes legacy lines without a header, the declarer is only added if it is not player one.
+    /// Positions with cards on the table can not be written.
+    pub fn legacy(writer: W) -> DatasetWriter<W> {
+        DatasetWriter {
+            writer,
+            legacy: true,
+        }
+    }
+
+    pub fn write(&mut self, position: &SolvedPosition) -> io::Result<()> {
+        if self.legacy {
+            return self.write_legacy(position);
+        }
+        let solver = position.solver.as_deref().unwrap_or("");
+        if solver.contains([',', '\n', '\r']) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("the solver name {solver} contains a separator")));
+        }
+        let table: Vec<String> = position.table.iter().map(|card| card.0.to_string()).collect();
+        let (p1, p2, p3) = position.player_cards;
+        writeln!(self.writer, "{},{},{},{},{},{},{},{},{},{solver}", p1.0, p2.0, p3.0, position.skat.0, position.declarer as u8,
+                 table.join(" "), position.current_player as u8, position.variant as u8, position.result)
+    }
+
+    fn write_legacy(&mut self, position: &SolvedPosition) -> io::Result<()> {
+        if !position.table.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "legacy files have no cards on the table"));
+        }
+        let (p1, p2, p3) = position.player_cards;
+        write!(self.writer, "{},{},{},{},{},{},{}", p1.0, p2.0, p3.0, position.skat.0,
+               position.current_player as u8, position.variant as u8, position.result)?;
+        if position.declarer != Player::One {
+            write!(self.writer, ",{}", position.declarer as u8)?;
+        }
+        writeln!(self.writer)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+///All positions of a versioned or legacy file
+pub fn read_dataset<P: AsRef<Path>>(path: P) -> Result<Vec<SolvedPosition>, DatasetError> {
+    DatasetReader::new(BufReader::new(File::open(path)?))?.collect()
+}
+
+pub fn write_dataset<P: AsRef<Path>>(path: P, positions: &[SolvedPosition]) -> io::Result<()> {
+    let mut writer = DatasetWriter::new(io::BufWriter::new(File::create(path)?))?;
+    for position in positions {
+        writer.write(position)?;
+    }
+    writer.into_inner().flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::dataset::{DatasetError, DatasetReader, DatasetWriter, read_dataset, SolvedPosition};
+    use crate::solver::bitboard::{BitCards, HEARTS_ASS, KREUZ_ASS, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_TEN};
+    use crate::solver::{Player, Variant};
+
+    #[test]
+    fn legacy_dataset() {
+        let positions = read_dataset("data/four_cards.txt").unwrap();
+        let input = std::fs::read_to_string("data/four_cards.txt").unwrap();
+        assert_eq!(positions.len(), input.lines().count());
+        for (position, line) in positions.iter().zip(input.lines()) {
+            let data: Vec<&str> = line.split(',').collect();
+            assert_eq!(position.player_cards.0, BitCards(data[0].parse::<u32>().unwrap()));
+            assert_eq!(position.current_player as u8, data[4].parse::<u8>().unwrap());
+            assert_eq!(position.result, data[6].parse::<u8>().unwrap());
+            assert_eq!(position.declarer, Player::One);
+            assert!(position.table.is_empty() && position.solver.is_none());
+        }
+        //the declarer can follow the result
+        let reader = DatasetReader::new(Cursor::new("1,2,4,0,1,3,0,2\n")).unwrap();
+        assert_eq!(reader.get_version(), None);
+        let positions: Vec<SolvedPosition> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(positions[0].declarer, Player::Three);
+        assert_eq!(positions[0].variant, Variant::Hearts);
+    }
+
+    #[test]
+    fn versioned_dataset() {
+        let mut position = SolvedPosition::new((KREUZ_ASS | HEARTS_ASS, KREUZ_TEN | PIQUS_ASS, PIQUS_TEN | KREUZ_SEVEN), BitCards(0), Player::Three, Variant::Grand, 21);
+        position.declarer = Player::Two;
+        position.table = vec![PIQUS_ASS];
+        position.solver = Some("enhanced".to_string());
+        let mut writer = DatasetWriter::new(Vec::new()).unwrap();
+        writer.write(&position).unwrap();
+        writer.write(&SolvedPosition::new((BitCards(1), BitCards(2), BitCards(4)), BitCards(8), Player::One, Variant::Null, 0)).unwrap();
+        let output = writer.into_inner();
+        let reader = DatasetReader::new(Cursor::new(&output)).unwrap();
+        assert_eq!(reader.get_version(), Some(1));
+        let positions: Vec<SolvedPosition> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(positions[0], position);
+        assert_eq!(positions[1].skat, BitCards(8));
+        let global_state = position.get_global_state();
+        assert!(position.get_local_state(&global_state).is_ok());
+        assert_eq!(position.get_remaining_cards().0.count_ones(), 5);
+        //a deal keeps the declarer but can not hold the table
+        assert!(position.get_deal().is_none());
+        position.table.clear();
+        assert_eq!(position.get_deal().unwrap().declarer, Player::Two);
+        //the columns can be in any order and optional ones left out
+        let input = "#skat-dataset version=1\nresult,variant,current_player,player_three,player_two,player_one,comment\n30,0,1,4,2,1,x\n";
+        let positions: Vec<SolvedPosition> = DatasetReader::new(Cursor::new(input)).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(positions[0], SolvedPosition::new((BitCards(1), BitCards(2), BitCards(4)), BitCards(0), Player::Two, Variant::Grand, 30));
+    }
+
+    #[test]
+    fn invalid_dataset() {
+        let error = |input: &'static str| match DatasetReader::new(Cursor::new(input)) {
+            Ok(reader) => reader.collect::<Result<Vec<_>, _>>().unwrap_err(),
+            Err(error) => error,
+        };
+        assert!(matches!(error("#skat-dataset version=2\n"), DatasetError::Version(2)));
+        assert!(matches!(error("#skat-dataset\n"), DatasetError::Header(_)));
+        assert!(matches!(error("#skat-dataset version=1\nplayer_one,player_two,player_three,variant,result\n"),
+            DatasetError::MissingField { line: 2, field: "current_player" }));
+        assert!(matches!(error("1,2,4,0,0,10,0\n"), DatasetError::InvalidField { line: 1, field: "variant", .. }));
+        assert!(matches!(error("1,2,4,0,3,0,0\n"), DatasetError::InvalidField { line: 1, field: "current_player", .. }));
+        assert!(matches!(error("1,2,4,0,0,0\n"), DatasetError::MissingField { line: 1, field: "result" }));
+        let mut position = SolvedPosition::new((BitCards(1), BitCards(2), BitCards(4)), BitCards(0), Player::One, Variant::Grand, 0);
+        position.solver = Some("a,b".to_string());
+        assert!(DatasetWriter::new(Vec::new()).unwrap().write(&position).is_err());
+    }
+}
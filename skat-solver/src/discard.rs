@@ -0,0 +1,185 @@
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use crate::solver::bitboard::{BitCard, BitCards};
+use crate::solver::synchronus::ab_tt_optimized::EnhancedSolver;
+use crate::solver::synchronus::local_state::LState;
+use crate::solver::{GlobalState, Player, Variant};
+
+///Two cards put into the skat with the score of the declarer after them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Discard {
+    pub skat: BitCards,
+    ///card points including the discarded cards, in null games tricks of the declarer.
+    /// Averaged over the samples if the other hands are not known.
+    pub value: f64,
+}
+
+///
+/// Tries every pair of cards the declarer can discard, the declarer's hand holds the two cards of the skat.
+/// All hands are known, the best discard comes first.
+pub fn best_discard(player_cards: (BitCards, BitCards, BitCards), declarer: Player, starting_player: Player, variant: Variant) -> Vec<Discard> {
+    let declarer_cards = get_hand(player_cards, declarer);
+    let mut discards: Vec<Discard> = get_pairs(declarer_cards).into_iter()
+        .map(|skat| Discard {
+            skat,
+            value: solve_discard(player_cards, skat, declarer, starting_player, variant) as f64,
+        })
+        .collect();
+    sort_discards(&mut discards, variant);
+    discards
+}
+
+///
+/// Like best_discard, but the hidden cards are dealt randomly to the opponents. Both get as many cards
+/// as the declarer keeps, hidden cards which are left are not in the game. Every discard is solved with the same samples.
+pub fn best_discard_sampled(declarer_cards: BitCards, hidden: BitCards, declarer: Player, starting_player: Player, variant: Variant, samples: usize, seed: u64) -> Vec<Discard> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut hidden: Vec<BitCard> = hidden.collect();
+    let hand_size = declarer_cards.0.count_ones() as usize - 2;
+    assert!(hidden.len() >= 2 * hand_size, "not enough hidden cards for the opponents");
+    let mut discards: Vec<Discard> = get_pairs(declarer_cards).into_iter()
+        .map(|skat| Discard { skat, value: 0.0 })
+        .collect();
+    for _ in 0..samples {
+        hidden.shuffle(&mut rng);
+        let first = hidden[..hand_size].iter().fold(BitCards(0), |hand, card| hand | *card);
+        let second = hidden[hand_size..2 * hand_size].iter().fold(BitCards(0), |hand, card| hand | *card);
+        let player_cards = match declarer {
+            Player::One => (declarer_cards, first, second),
+            Player::Two => (second, declarer_cards, first),
+            Player::Three => (first, second, declarer_cards),
+        };
+        for discard in &mut discards {
+            discard.value += solve_discard(player_cards, discard.skat, declarer, starting_player, variant) as f64;
+        }
+    }
+    for discard in &mut discards {
+        discard.value /= samples as f64;
+    }
+    sort_discards(&mut discards, variant);
+    discards
+}
+
+fn solve_discard(player_cards: (BitCards, BitCards, BitCards), skat: BitCards, declarer: Player, starting_player: Player, variant: Variant) -> u8 {
+    let mut hands = player_cards;
+    match declarer {
+        Player::One => hands.0 = BitCards(hands.0.0 & !skat.0),
+        Player::Two => hands.1 = BitCards(hands.1.0 & !skat.0),
+        Player::Three => hands.2 = BitCards(hands.2.0 & !skat.0),
+    }
+    let global_state = GlobalState::new(hands, skat, declarer, variant);
+    let mut solver = EnhancedSolver::new(global_state);
+    solver.solve(LState::new(hands.0 | hands.1 | hands.2, starting_player))
+}
+
+fn get_hand(player_cards: (BitCards, BitCards, BitCards), player: Player) -> BitCards {
+    match player {
+        Player::One => player_cards.0,
+        Player::Two => player_cards.1,
+        Player::Three => player_cards.2,
+    }
+}
+
+///Every set of two cards, 66 for a hand with the skat
+fn get_pairs(cards: BitCards) -> Vec<BitCards> {
+    let mut pairs = Vec::new();
+    let mut rest = cards;
+    while let Some(first) = rest.next() {
+        for second in rest {
+            pairs.push(first | second);
+        }
+    }
+    pairs
+}
+
+///In null games the declarer wants as few tricks as possible
+fn sort_discards(discards: &mut [Discard], variant: Variant) {
+    if variant.is_null() {
+        discards.sort_by(|a, b| a.value.total_cmp(&b.value));
+    } else {
+        discards.sort_by(|a, b| b.value.total_cmp(&a.value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::read_dataset;
+    use crate::discard::{best_discard, best_discard_sampled};
+    use crate::solver::bitboard::{BitCard, BitCards, HEARTS_ASS, HEARTS_JACK, HEARTS_KING, HEARTS_SEVEN, KARO_ASS, KREUZ_ASS, KREUZ_JACK, KREUZ_TEN, PIQUS_SEVEN, PIQUS_TEN};
+    use crate::solver::synchronus::ab_tt::DefaultSolver;
+    use crate::solver::synchronus::local_state::LState;
+    use crate::solver::{GlobalState, Player, Variant};
+
+    #[test]
+    fn discard_all_pairs() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(10) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            //the declarer picks up the two highest cards which are not in the game
+            let mut out = BitCards(!(p1 | p2 | p3).0);
+            let skat = out.next().unwrap() | out.next().unwrap();
+            let declarer_cards = p1 | skat;
+            let discards = best_discard((declarer_cards, p2, p3), Player::One, current_player, variant);
+            assert_eq!(discards.len(), 21);
+            assert!(discards.iter().all(|discard| discard.skat.0.count_ones() == 2 && discard.skat.0 & !declarer_cards.0 == 0));
+            if variant.is_null() {
+                assert!(discards.windows(2).all(|pair| pair[0].value <= pair[1].value));
+            } else {
+                assert!(discards.windows(2).all(|pair| pair[0].value >= pair[1].value));
+            }
+            //every value is the solved score without the discarded cards
+            for discard in discards.iter().take(3) {
+                let hand = BitCards(declarer_cards.0 & !discard.skat.0);
+                let global_state = GlobalState::new((hand, p2, p3), discard.skat, Player::One, variant);
+                let mut solver = DefaultSolver::new(global_state);
+                let score = solver.solve(LState::new(hand | p2 | p3, current_player));
+                assert_eq!(discard.value, score as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn discard_sampled() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(5) {
+            let (p1, p2, p3) = position.player_cards;
+            let variant = position.variant;
+            let mut out = BitCards(!(p1 | p2 | p3).0);
+            let skat = out.next().unwrap() | out.next().unwrap();
+            //the declarer sits in the second seat
+            let declarer_cards = p1 | skat;
+            let discards = best_discard_sampled(declarer_cards, p2 | p3, Player::Two, Player::One, variant, 4, 21);
+            assert_eq!(discards.len(), 21);
+            assert_eq!(discards, best_discard_sampled(declarer_cards, p2 | p3, Player::Two, Player::One, variant, 4, 21));
+        }
+    }
+
+    #[test]
+    fn discard_sampled_values() {
+        let declarer_cards = KREUZ_JACK | HEARTS_ASS | HEARTS_SEVEN | PIQUS_TEN;
+        let hidden: Vec<BitCard> = (KREUZ_ASS | KREUZ_TEN | HEARTS_KING | HEARTS_JACK | PIQUS_SEVEN | KARO_ASS).collect();
+        //the opponents get two cards each, the other two hidden cards are not in the game
+        let pairs: Vec<BitCards> = (0..6).flat_map(|i| (i + 1..6).map(move |j| (i, j)))
+            .map(|(i, j)| hidden[i] | hidden[j])
+            .collect();
+        for declarer in [Player::Two, Player::Three] {
+            let mut deals = Vec::new();
+            for first in &pairs {
+                for second in pairs.iter().filter(|second| second.0 & first.0 == 0) {
+                    let player_cards = match declarer {
+                        Player::Two => (*first, declarer_cards, *second),
+                        _ => (*first, *second, declarer_cards),
+                    };
+                    deals.push(best_discard(player_cards, declarer, Player::One, Variant::Hearts));
+                }
+            }
+            //a single sample is one of the deals
+            for seed in 0..5 {
+                let hidden_cards = hidden.iter().fold(BitCards(0), |cards, card| cards | *card);
+                let discards = best_discard_sampled(declarer_cards, hidden_cards, declarer, Player::One, Variant::Hearts, 1, seed);
+                assert!(deals.contains(&discards), "{discards:?}");
+            }
+        }
+    }
+}
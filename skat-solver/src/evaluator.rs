@@ -0,0 +1,172 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use crate::knowledge::PossibleDeal;
+use crate::pimc::{PlayerView, solve_moves};
+use crate::solver::bitboard::BitCard;
+
+///Results of one card over all evaluated deals, the scores are final scores of the declarer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveStatistics {
+    pub card: BitCard,
+    ///card points including the skat, in null games tricks of the declarer
+    pub expected: f64,
+    ///share of the deals the declarer wins, 61 points or no trick in null games
+    pub win_probability: f64,
+    ///share of the deals the declarer gets at least 90 points, always zero in null games
+    pub schneider_probability: f64,
+    ///worst score over all deals from the view of the player to move
+    pub worst_case: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    ///the best card for the player to move comes first
+    pub moves: Vec<MoveStatistics>,
+    pub deals: usize,
+    ///every consistent deal was solved, otherwise the deals were sampled
+    pub exact: bool,
+}
+
+///
+/// Expected scores under imperfect information. If there are at most max_deals consistent deals
+/// every one of them is solved, else the given amount of samples is drawn.
+pub struct ExactEvaluator {
+    max_deals: u64,
+    samples: usize,
+    seed: u64,
+}
+
+impl ExactEvaluator {
+    pub fn new(max_deals: u64, samples: usize, seed: u64) -> ExactEvaluator {
+        ExactEvaluator {
+            max_deals,
+            samples,
+            seed,
+        }
+    }
+
+    pub fn evaluate(&self, view: &PlayerView) -> Evaluation {
+        debug_assert_eq!(view.get_current_player(), view.player);
+        let knowledge = view.get_knowledge();
+        let exact = knowledge.count_deals() <= self.max_deals;
+        let deals: Vec<PossibleDeal> = if exact {
+            knowledge.enumerate_deals()
+        } else {
+            let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+            (0..self.samples).map_while(|_| knowledge.sample_deal(&mut rng)).collect()
+        };
+        let maximizes = (view.player == view.declarer) != view.variant.is_null();
+        let mut moves: Vec<MoveStatistics> = Vec::new();
+        for deal in &deals {
+            for (card, score) in solve_moves(view, knowledge, deal) {
+                let wins = if view.variant.is_null() { score == 0 } else { score > 60 };
+                let schneider = !view.variant.is_null() && score >= 90;
+                match moves.iter_mut().find(|statistics| statistics.card == card) {
+                    Some(statistics) => {
+                        statistics.expected += score as f64;
+                        statistics.win_probability += wins as u8 as f64;
+                        statistics.schneider_probability += schneider as u8 as f64;
+                        statistics.worst_case = if maximizes {
+                            statistics.worst_case.min(score)
+                        } else {
+                            statistics.worst_case.max(score)
+                        };
+                    }
+                    None => moves.push(MoveStatistics {
+                        card,
+                        expected: score as f64,
+                        win_probability: wins as u8 as f64,
+                        schneider_probability: schneider as u8 as f64,
+                        worst_case: score,
+                    }),
+                }
+            }
+        }
+        let amount = deals.len() as f64;
+        for statistics in &mut moves {
+            statistics.expected /= amount;
+            statistics.win_probability /= amount;
+            statistics.schneider_probability /= amount;
+        }
+        if maximizes {
+            moves.sort_by(|a, b| b.expected.total_cmp(&a.expected));
+        } else {
+            moves.sort_by(|a, b| a.expected.total_cmp(&b.expected));
+        }
+        Evaluation {
+            moves,
+            deals: deals.len(),
+            exact,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::read_dataset;
+    use crate::evaluator::ExactEvaluator;
+    use crate::pimc::tests::get_view_to_move;
+    use crate::solver::bitstates::{BitGlobal, BitLocal};
+    use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use crate::solver::Player;
+
+    #[test]
+    fn evaluator_exact() {
+        for position in read_dataset("data/six_cards.txt").unwrap().into_iter().take(30) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, Player::One, variant));
+            let variation = solver.principal_variation(BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state));
+            //the view of the player to move after two tricks and one card
+            let view = get_view_to_move([p1, p2, p3], current_player, variant, &variation[..7]);
+            let player = view.player;
+            let evaluation = ExactEvaluator::new(1000, 10, 5).evaluate(&view);
+            assert!(evaluation.exact);
+            assert_eq!(evaluation.deals as u64, view.get_knowledge().count_deals());
+            //the real deal is one of the evaluated ones
+            let mut real_state = BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state);
+            for card in &variation[..7] {
+                real_state = real_state.get_next_states(&solver.global_state).into_iter()
+                    .find(|(next_state, _)| real_state.get_played_card(next_state, &solver.global_state) == *card).unwrap().0;
+            }
+            let real_scores = solver.analyze_moves(real_state);
+            let maximizes = (player == Player::One) != variant.is_null();
+            assert_eq!(evaluation.moves.len(), real_scores.len());
+            for statistics in &evaluation.moves {
+                assert!((0.0..=1.0).contains(&statistics.win_probability));
+                assert!(statistics.schneider_probability <= statistics.win_probability);
+                if maximizes {
+                    assert!(statistics.worst_case as f64 <= statistics.expected);
+                } else {
+                    assert!(statistics.worst_case as f64 >= statistics.expected);
+                }
+            }
+            //only the points of the remaining cards are compared, the real solver does not know the earlier tricks
+            let offset = view.get_knowledge().declarer_score;
+            for (card, score) in real_scores {
+                let statistics = evaluation.moves.iter().find(|statistics| statistics.card == card).unwrap();
+                if maximizes {
+                    assert!(statistics.worst_case <= score + offset);
+                } else {
+                    assert!(statistics.worst_case >= score + offset);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn evaluator_sampling() {
+        for position in read_dataset("data/six_cards.txt").unwrap().into_iter().take(10) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let view = get_view_to_move([p1, p2, p3], current_player, variant, &[]);
+            let evaluator = ExactEvaluator::new(10, 20, 9);
+            let evaluation = evaluator.evaluate(&view);
+            assert!(!evaluation.exact);
+            assert_eq!(evaluation.deals, 20);
+            assert_eq!(evaluation, evaluator.evaluate(&view));
+        }
+    }
+}
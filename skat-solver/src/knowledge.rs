@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better};
+use crate::solver::{calculate_current_suit_mask, Player, TrickError, Variant};
+
+///One distribution of the hidden cards, the hands only contain cards which were not played yet
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PossibleDeal {
+    pub hands: [BitCards; 3],
+    pub skat: BitCards,
+}
+
+///Hidden cards which may lie at the same places, only the amount per place matters for counting
+#[derive(Clone, Debug)]
+struct Group {
+    cards: Vec<BitCard>,
+    //first and second opponent of the observer, skat
+    allowed: [bool; 3],
+}
+
+///
+/// What one player knows about the hidden cards. Every played card is observed in order,
+/// a player who does not follow suit can not hold any card of the suit anymore.
+#[derive(Clone, Debug)]
+pub struct Knowledge {
+    observer: Player,
+    declarer: Player,
+    variant: Variant,
+    current_player: Player,
+    ///cards of the open trick with the players who played them
+    pub(crate) trick: Vec<(BitCard, Player)>,
+    ///card points or in null games tricks of the declarer in the finished tricks
+    pub(crate) declarer_score: u8,
+    ///cards every player may still hold, the own hand is exact
+    possible: [BitCards; 3],
+    ///cards which are known to be in a hand, e.g. the cards of an ouvert declarer
+    known: [BitCards; 3],
+    ///hidden cards which may lie in the skat
+    skat_possible: BitCards,
+    ///the skat if it is known to the observer
+    skat: BitCards,
+    hand_sizes: [u32; 3],
+}
+
+impl Knowledge {
+    ///hidden are all cards the observer can not see, including an unknown skat
+    pub fn new(observer: Player, declarer: Player, starting_player: Player, variant: Variant, hand: BitCards, hidden: BitCards) -> Knowledge {
+        let mut possible = [hidden; 3];
+        possible[observer as usize] = hand;
+        let mut known = [BitCards(0); 3];
+        known[observer as usize] = hand;
+        Knowledge {
+            observer,
+            declarer,
+            variant,
+            current_player: starting_player,
+            trick: Vec::new(),
+            declarer_score: 0,
+            possible,
+            known,
+            skat_possible: hidden,
+            skat: BitCards(0),
+            hand_sizes: [hand.0.count_ones(); 3],
+        }
+    }
+
+    ///The skat is known to the declarer after picking it up
+    pub fn with_skat(mut self, skat: BitCards) -> Knowledge {
+        self.skat = skat;
+        self.remove_cards(skat.0);
+        self.skat_possible = BitCards(0);
+        self
+    }
+
+    ///
+    /// The cards of the open trick were played before the observer got to know the game, in order
+    /// starting with the starting player. The hand does not contain the own card of the trick,
+    /// so the players who played to the trick hold one card less than the others.
+    pub fn with_trick(mut self, played_cards: &[BitCard]) -> Result<Knowledge, TrickError> {
+        debug_assert!(self.trick.is_empty());
+        if played_cards.len() > 2 {
+            return Err(TrickError::TooManyCards);
+        }
+        let mut player = self.current_player;
+        let mut observer_card = None;
+        for card in played_cards {
+            if player == self.observer {
+                observer_card = Some(*card);
+            }
+            player = player.get_next_player();
+        }
+        let hand_size = self.hand_sizes[self.observer as usize] + observer_card.is_some() as u32;
+        self.hand_sizes = [hand_size; 3];
+        if let Some(card) = observer_card {
+            if self.known[self.observer as usize].0 & card.0 != 0 {
+                return Err(TrickError::CardNotPlayed(card));
+            }
+            self.possible[self.observer as usize] = self.possible[self.observer as usize] | card;
+        }
+        for card in played_cards {
+            self.observe(*card)?;
+        }
+        Ok(self)
+    }
+
+    ///The player holds the cards, e.g. the hand of an ouvert declarer
+    pub fn add_known_cards(&mut self, player: Player, cards: BitCards) {
+        self.remove_cards(cards.0);
+        self.possible[player as usize] = self.possible[player as usize] | cards;
+        self.known[player as usize] = self.known[player as usize] | cards;
+    }
+
+    fn remove_cards(&mut self, cards: u32) {
+        for player in 0..3 {
+            self.possible[player].0 &= !cards;
+            self.known[player].0 &= !cards;
+        }
+        self.skat_possible.0 &= !cards;
+    }
+
+    ///
+    /// Adds the next card of the game, the player who played it follows from the earlier tricks.
+    /// A card the player can not hold is rejected and nothing is changed.
+    pub fn observe(&mut self, card: BitCard) -> Result<(), TrickError> {
+        let player = self.current_player;
+        if card.0.count_ones() != 1 {
+            return Err(TrickError::NotASingleCard(card));
+        }
+        if self.possible[player as usize].0 & card.0 == 0 {
+            return Err(TrickError::WrongPlayer(card));
+        }
+        if self.hand_sizes[player as usize] == 0 {
+            return Err(TrickError::WrongCardCount);
+        }
+        if let Some((first, _)) = self.trick.first() {
+            let suit = calculate_current_suit_mask(*first, &self.variant);
+            if card.0 & suit.0 == 0 {
+                self.possible[player as usize].0 &= !suit.0;
+            }
+        }
+        self.remove_cards(card.0);
+        self.hand_sizes[player as usize] -= 1;
+        self.trick.push((card, player));
+        self.current_player = player.get_next_player();
+        if self.trick.len() < 3 {
+            return Ok(());
+        }
+        let (winner_card, points) = calculate_who_won_better(self.trick[0].0, self.trick[1].0, self.trick[2].0, &self.variant);
+        let winner = self.trick.iter().find(|(card, _)| *card == winner_card).unwrap().1;
+        if winner == self.declarer {
+            self.declarer_score += points;
+        }
+        self.current_player = winner;
+        self.trick.clear();
+        Ok(())
+    }
+
+    pub fn get_current_player(&self) -> Player {
+        self.current_player
+    }
+
+    pub fn get_declarer(&self) -> Player {
+        self.declarer
+    }
+
+    pub fn get_variant(&self) -> Variant {
+        self.variant
+    }
+
+    ///Cards the player may still hold
+    pub fn get_possible_cards(&self, player: Player) -> BitCards {
+        self.possible[player as usize]
+    }
+
+    pub fn get_hand_size(&self, player: Player) -> u32 {
+        self.hand_sizes[player as usize]
+    }
+
+    fn get_opponents(&self) -> [Player; 2] {
+        [self.observer.get_next_player(), self.observer.get_previous_player()]
+    }
+
+    ///Free places of both opponents and the skat, none if the known cards do not fit
+    fn get_capacities(&self) -> Option<[u32; 3]> {
+        let [first, second] = self.get_opponents();
+        let first = self.hand_sizes[first as usize].checked_sub(self.known[first as usize].0.count_ones())?;
+        let second = self.hand_sizes[second as usize].checked_sub(self.known[second as usize].0.count_ones())?;
+        let unknown = self.get_unknown_cards().0.count_ones();
+        let skat = unknown.checked_sub(first + second)?;
+        Some([first, second, skat])
+    }
+
+    ///Hidden cards whose place is not known
+    fn get_unknown_cards(&self) -> BitCards {
+        let [first, second] = self.get_opponents();
+        let hidden = self.possible[first as usize] | self.possible[second as usize] | self.skat_possible;
+        BitCards(hidden.0 & !(self.known[first as usize] | self.known[second as usize]).0)
+    }
+
+    fn get_groups(&self) -> Vec<Group> {
+        let [first, second] = self.get_opponents();
+        let places = [self.possible[first as usize].0, self.possible[second as usize].0, self.skat_possible.0];
+        let mut groups: Vec<Group> = Vec::new();
+        for card in self.get_unknown_cards() {
+            let allowed = places.map(|place| place & card.0 != 0);
+            match groups.iter_mut().find(|group| group.allowed == allowed) {
+                Some(group) => group.cards.push(card),
+                None => groups.push(Group { cards: vec![card], allowed }),
+            }
+        }
+        groups
+    }
+
+    ///Number of deals consistent with everything observed
+    pub fn count_deals(&self) -> u64 {
+        let Some(capacities) = self.get_capacities() else {
+            return 0;
+        };
+        let groups = self.get_groups();
+        count_distributions(&groups, capacities, &mut HashMap::new())
+    }
+
+    ///Every deal consistent with everything observed, the amount can be checked with count_deals first
+    pub fn enumerate_deals(&self) -> Vec<PossibleDeal> {
+        let mut deals = Vec::new();
+        if let Some(capacities) = self.get_capacities() {
+            let groups = self.get_groups();
+            self.enumerate_groups(&groups, capacities, [BitCards(0); 3], &mut deals);
+        }
+        deals
+    }
+
+    fn enumerate_groups(&self, groups: &[Group], capacities: [u32; 3], places: [BitCards; 3], deals: &mut Vec<PossibleDeal>) {
+        let Some((group, rest)) = groups.split_first() else {
+            if capacities == [0; 3] {
+                deals.push(self.create_deal(places));
+            }
+            return;
+        };
+        for amounts in get_splits(group, capacities) {
+            for_each_split(&group.cards, amounts, [BitCards(0); 3], &mut |split| {
+                let places = [places[0] | split[0], places[1] | split[1], places[2] | split[2]];
+                let capacities = [capacities[0] - amounts[0], capacities[1] - amounts[1], capacities[2] - amounts[2]];
+                self.enumerate_groups(rest, capacities, places, deals);
+            });
+        }
+    }
+
+    ///Draws one of the consistent deals, every deal has the same probability
+    pub fn sample_deal<R: Rng>(&self, rng: &mut R) -> Option<PossibleDeal> {
+        let mut capacities = self.get_capacities()?;
+        let groups = self.get_groups();
+        let mut cache = HashMap::new();
+        if count_distributions(&groups, capacities, &mut cache) == 0 {
+            return None;
+        }
+        let mut places = [BitCards(0); 3];
+        for (i, group) in groups.iter().enumerate() {
+            //the amounts per place are drawn by the number of deals they allow
+            let mut target = rng.gen_range(0..count_distributions(&groups[i..], capacities, &mut cache));
+            let mut chosen = [0; 3];
+            for amounts in get_splits(group, capacities) {
+                let remaining = [capacities[0] - amounts[0], capacities[1] - amounts[1], capacities[2] - amounts[2]];
+                let ways = get_multinomial(group.cards.len() as u32, amounts) * count_distributions(&groups[i + 1..], remaining, &mut cache);
+                if target < ways {
+                    chosen = amounts;
+                    break;
+                }
+                target -= ways;
+            }
+            let mut cards = group.cards.clone();
+            cards.shuffle(rng);
+            let mut cards = cards.into_iter();
+            for (place, amount) in chosen.iter().enumerate() {
+                for card in cards.by_ref().take(*amount as usize) {
+                    places[place] = places[place] | card;
+                }
+                capacities[place] -= amount;
+            }
+        }
+        Some(self.create_deal(places))
+    }
+
+    fn create_deal(&self, places: [BitCards; 3]) -> PossibleDeal {
+        let [first, second] = self.get_opponents();
+        let mut hands = self.known;
+        hands[first as usize] = hands[first as usize] | places[0];
+        hands[second as usize] = hands[second as usize] | places[1];
+        PossibleDeal {
+            hands,
+            skat: self.skat | places[2],
+        }
+    }
+}
+
+///Amounts of the group's cards for each place which fit into the free places
+fn get_splits(group: &Group, capacities: [u32; 3]) -> Vec<[u32; 3]> {
+    let size = group.cards.len() as u32;
+    let mut splits = Vec::new();
+    let max_first = if group.allowed[0] { size.min(capacities[0]) } else { 0 };
+    for first in 0..=max_first {
+        let max_second = if group.allowed[1] { (size - first).min(capacities[1]) } else { 0 };
+        for second in 0..=max_second {
+            let skat = size - first - second;
+            if (skat > 0 && !group.allowed[2]) || skat > capacities[2] {
+                continue;
+            }
+            splits.push([first, second, skat]);
+        }
+    }
+    splits
+}
+
+///Number of ways to put the cards of the groups into the free places,
+/// the results are cached by the amount of groups left and the free places
+fn count_distributions(groups: &[Group], capacities: [u32; 3], cache: &mut HashMap<(usize, [u32; 3]), u64>) -> u64 {
+    let Some((group, rest)) = groups.split_first() else {
+        return (capacities == [0; 3]) as u64;
+    };
+    if let Some(count) = cache.get(&(groups.len(), capacities)) {
+        return *count;
+    }
+    let count = get_splits(group, capacities).into_iter()
+        .map(|amounts| {
+            let remaining = [capacities[0] - amounts[0], capacities[1] - amounts[1], capacities[2] - amounts[2]];
+            get_multinomial(group.cards.len() as u32, amounts) * count_distributions(rest, remaining, cache)
+        })
+        .sum();
+    cache.insert((groups.len(), capacities), count);
+    count
+}
+
+fn get_multinomial(size: u32, amounts: [u32; 3]) -> u64 {
+    get_binomial(size, amounts[0]) * get_binomial(size - amounts[0], amounts[1])
+}
+
+fn get_binomial(n: u32, k: u32) -> u64 {
+    let mut result = 1u64;
+    for i in 0..k.min(n - k) as u64 {
+        result = result * (n as u64 - i) / (i + 1);
+    }
+    result
+}
+
+///Calls f with every way to put the cards into the places with the given amounts
+fn for_each_split(cards: &[BitCard], amounts: [u32; 3], split: [BitCards; 3], f: &mut impl FnMut([BitCards; 3])) {
+    let Some((card, rest)) = cards.split_first() else {
+        f(split);
+        return;
+    };
+    for place in 0..3 {
+        if amounts[place] == 0 {
+            continue;
+        }
+        let mut amounts = amounts;
+        amounts[place] -= 1;
+        let mut split = split;
+        split[place] = split[place] | *card;
+        for_each_split(rest, amounts, split, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::dataset::read_dataset;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use crate::knowledge::Knowledge;
+    use crate::solver::bitboard::{BitCard, BitCards, HEARTS_ASS, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_SEVEN, KREUZ_ASS, KREUZ_JACK, KREUZ_KING, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_SEVEN};
+    use crate::solver::bitstates::{BitGlobal, BitLocal};
+    use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use crate::solver::{Player, TrickError, Variant};
+
+    #[test]
+    fn knowledge_voids() {
+        let player_one = KREUZ_ASS | HEARTS_ASS;
+        let player_two = KREUZ_KING | HEARTS_SEVEN;
+        let player_three = PIQUS_ASS | KARO_SEVEN;
+        let mut knowledge = Knowledge::new(Player::One, Player::One, Player::One, Variant::Grand, player_one, player_two | player_three | KREUZ_TEN | PIQUS_SEVEN);
+        assert_eq!(knowledge.count_deals(), 90);
+        knowledge.observe(KREUZ_ASS).unwrap();
+        knowledge.observe(KREUZ_KING).unwrap();
+        knowledge.observe(PIQUS_ASS).unwrap();
+        //player three is void in kreuz and player one won the trick
+        assert_eq!(knowledge.get_possible_cards(Player::Three).0 & KREUZ_TEN.0, 0);
+        assert_eq!(knowledge.get_current_player(), Player::One);
+        assert_eq!(knowledge.declarer_score, 26);
+        assert_eq!(knowledge.get_hand_size(Player::Two), 1);
+        let deals = knowledge.enumerate_deals();
+        assert_eq!(deals.len() as u64, knowledge.count_deals());
+        assert_eq!(deals.len(), 9);
+        for deal in deals {
+            assert_eq!(deal.hands[0], BitCards(HEARTS_ASS.0));
+            assert_eq!(deal.hands[2].0 & KREUZ_TEN.0, 0);
+            assert_eq!(deal.skat.0.count_ones(), 2);
+        }
+        //the cards of an ouvert declarer are known, only the skat and player three are open
+        let mut knowledge = Knowledge::new(Player::Two, Player::One, Player::One, Variant::Grand, player_two, player_one | player_three | KREUZ_JACK);
+        knowledge.add_known_cards(Player::One, player_one);
+        knowledge.observe(KREUZ_ASS).unwrap();
+        assert_eq!(knowledge.count_deals(), 3);
+    }
+
+    #[test]
+    fn knowledge_open_trick() {
+        let player_two = KREUZ_KING | HEARTS_SEVEN;
+        let player_three = PIQUS_ASS | KARO_SEVEN;
+        //player one led before player two got to know the game
+        let knowledge = Knowledge::new(Player::Two, Player::One, Player::One, Variant::Grand, player_two, player_three | HEARTS_ASS | KREUZ_ASS | KREUZ_TEN | PIQUS_SEVEN)
+            .with_trick(&[KREUZ_ASS]).unwrap();
+        assert_eq!(knowledge.get_current_player(), Player::Two);
+        assert_eq!([Player::One, Player::Two, Player::Three].map(|player| knowledge.get_hand_size(player)), [1, 2, 2]);
+        assert_eq!(knowledge.count_deals(), 30);
+        //the own card of the trick is not in the hand anymore
+        let knowledge = Knowledge::new(Player::One, Player::One, Player::One, Variant::Grand, BitCards(HEARTS_ASS.0), player_two | player_three | KREUZ_TEN | PIQUS_SEVEN)
+            .with_trick(&[KREUZ_ASS]).unwrap();
+        assert_eq!(knowledge.get_hand_size(Player::One), 1);
+        assert_eq!(knowledge.count_deals(), 90);
+        let knowledge = Knowledge::new(Player::One, Player::One, Player::One, Variant::Grand, BitCards(HEARTS_ASS.0), player_two | player_three);
+        assert_eq!(knowledge.with_trick(&[HEARTS_ASS]).unwrap_err(), TrickError::CardNotPlayed(HEARTS_ASS));
+    }
+
+    #[test]
+    fn knowledge_rejected_cards() {
+        let mut knowledge = Knowledge::new(Player::One, Player::One, Player::One, Variant::Grand, BitCards(KREUZ_SEVEN.0), KREUZ_ASS | PIQUS_ASS | HEARTS_ASS | HEARTS_SEVEN);
+        //the card is in the own hand
+        assert_eq!(knowledge.observe(KREUZ_ASS), Err(TrickError::WrongPlayer(KREUZ_ASS)));
+        let cards = BitCard(KREUZ_SEVEN.0 | HEARTS_ASS.0);
+        assert_eq!(knowledge.observe(cards), Err(TrickError::NotASingleCard(cards)));
+        assert_eq!(knowledge.get_current_player(), Player::One);
+        knowledge.observe(KREUZ_SEVEN).unwrap();
+        knowledge.observe(KREUZ_ASS).unwrap();
+        knowledge.observe(PIQUS_ASS).unwrap();
+        //player two took the last trick and has no cards left
+        assert_eq!(knowledge.get_current_player(), Player::Two);
+        assert_eq!(knowledge.observe(HEARTS_ASS), Err(TrickError::WrongCardCount));
+        assert_eq!(knowledge.get_current_player(), Player::Two);
+        assert_eq!(knowledge.get_hand_size(Player::Two), 0);
+    }
+
+    #[test]
+    fn knowledge_uniform_samples() {
+        let player_one = KREUZ_ASS | HEARTS_ASS | KARO_ASS;
+        let hidden = KREUZ_KING | KREUZ_SEVEN | HEARTS_SEVEN | HEARTS_TEN | PIQUS_ASS | PIQUS_SEVEN | KARO_SEVEN | KREUZ_TEN;
+        let mut knowledge = Knowledge::new(Player::One, Player::One, Player::One, Variant::Grand, player_one, hidden);
+        knowledge.observe(HEARTS_ASS).unwrap();
+        knowledge.observe(HEARTS_SEVEN).unwrap();
+        knowledge.observe(KREUZ_KING).unwrap();
+        let deals = knowledge.enumerate_deals();
+        assert_eq!(deals.len() as u64, knowledge.count_deals());
+        let mut counts: HashMap<_, usize> = deals.iter().map(|deal| (*deal, 0)).collect();
+        let mut rng = ChaCha8Rng::seed_from_u64(11);
+        let samples = deals.len() * 1000;
+        for _ in 0..samples {
+            let deal = knowledge.sample_deal(&mut rng).unwrap();
+            *counts.get_mut(&deal).unwrap() += 1;
+        }
+        for count in counts.values() {
+            assert!(*count > 800 && *count < 1200, "{count}");
+        }
+    }
+
+    #[test]
+    fn knowledge_real_deal_possible() {
+        for position in read_dataset("data/six_cards.txt").unwrap().into_iter().take(50) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, Player::One, variant));
+            let variation = solver.principal_variation(BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state));
+            let mut knowledge = Knowledge::new(Player::Two, Player::One, current_player, variant, p2, p1 | p3);
+            let mut hands = [p1, p2, p3];
+            for card in &variation[..8] {
+                knowledge.observe(*card).unwrap();
+                for hand in &mut hands {
+                    hand.0 &= !card.0;
+                }
+                let deals = knowledge.enumerate_deals();
+                assert_eq!(deals.len() as u64, knowledge.count_deals());
+                assert!(deals.iter().any(|deal| deal.hands == hands));
+                assert!(deals.iter().all(|deal| deal.skat.0 == 0 && deal.hands[1] == hands[1]));
+            }
+        }
+    }
+}
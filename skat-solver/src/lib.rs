@@ -1,6 +1,12 @@
-#![feature(exclusive_range_pattern)]
 #![allow(dead_code)]
 
 pub mod solver;
-
-
+pub mod scoring;
+pub mod knowledge;
+pub mod pimc;
+pub mod evaluator;
+pub mod discard;
+pub mod contract;
+pub mod bidding;
+pub mod dataset;
+pub mod binary_dataset;
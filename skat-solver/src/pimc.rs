@@ -0,0 +1,235 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use crate::knowledge::{Knowledge, PossibleDeal};
+use crate::solver::bitboard::{BitCard, BitCards};
+use crate::solver::bitstates::{BitGlobal, BitLocal};
+use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+use crate::solver::{Player, TrickError, Variant};
+
+///
+/// Everything one player knows about a running game: the own hand, the skat if they are the declarer
+/// and every card played so far.
+#[derive(Clone, Debug)]
+pub struct PlayerView {
+    pub player: Player,
+    pub declarer: Player,
+    pub variant: Variant,
+    ///player who played the first card of the game
+    pub starting_player: Player,
+    ///own cards which were not played yet
+    pub hand: BitCards,
+    ///the skat, empty if it is not known
+    pub skat: BitCards,
+    ///cards of the other hands and of an unknown skat
+    pub hidden: BitCards,
+    ///all played cards in the order they were played
+    pub played_cards: Vec<BitCard>,
+    knowledge: Knowledge,
+}
+
+impl PlayerView {
+    ///View at the start of a full game, every card which is not in the hand is hidden
+    pub fn new(player: Player, declarer: Player, starting_player: Player, variant: Variant, hand: BitCards) -> PlayerView {
+        PlayerView {
+            player,
+            declarer,
+            variant,
+            starting_player,
+            hand,
+            skat: BitCards(0),
+            hidden: BitCards(!hand.0),
+            played_cards: Vec::new(),
+            knowledge: Knowledge::new(player, declarer, starting_player, variant, hand, BitCards(!hand.0)),
+        }
+    }
+
+    ///The declarer knows the skat after picking it up or putting it down
+    pub fn with_skat(mut self, skat: BitCards) -> PlayerView {
+        debug_assert!(self.played_cards.is_empty());
+        self.skat = skat;
+        self.hidden = BitCards(self.hidden.0 & !skat.0);
+        self.knowledge = self.knowledge.with_skat(skat);
+        self
+    }
+
+    ///The player holds the cards, e.g. the defenders see the hand of an ouvert declarer
+    pub fn with_known_cards(mut self, player: Player, cards: BitCards) -> PlayerView {
+        debug_assert!(self.played_cards.is_empty());
+        self.knowledge.add_known_cards(player, cards);
+        self
+    }
+
+    ///Only the given cards are hidden, the others are not part of the game
+    pub fn with_hidden(mut self, hidden: BitCards) -> PlayerView {
+        debug_assert!(self.played_cards.is_empty());
+        self.hidden = hidden;
+        self.knowledge = Knowledge::new(self.player, self.declarer, self.starting_player, self.variant, self.hand, hidden)
+            .with_skat(self.skat);
+        self
+    }
+
+    ///Adds the next card of the game, no matter who played it. A card the player can not hold is rejected.
+    pub fn play(&mut self, card: BitCard) -> Result<(), TrickError> {
+        self.knowledge.observe(card)?;
+        self.hand = BitCards(self.hand.0 & !card.0);
+        self.hidden = BitCards(self.hidden.0 & !card.0);
+        self.played_cards.push(card);
+        Ok(())
+    }
+
+    pub fn get_current_player(&self) -> Player {
+        self.knowledge.get_current_player()
+    }
+
+    ///Voids and known cards of the other players derived from the play
+    pub fn get_knowledge(&self) -> &Knowledge {
+        &self.knowledge
+    }
+}
+
+///Aggregated result of one card over all samples
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveEvaluation {
+    pub card: BitCard,
+    ///average final score of the declarer, card points including the skat or tricks in null games
+    pub average: f64,
+    ///samples in which the card was one of the best cards
+    pub best_count: usize,
+}
+
+///
+/// Perfect information monte carlo: the hidden cards are dealt randomly a few times,
+/// every sample is solved double dummy and the scores of the cards are averaged.
+pub struct Pimc {
+    samples: usize,
+    seed: u64,
+}
+
+impl Pimc {
+    pub fn new(samples: usize, seed: u64) -> Pimc {
+        Pimc {
+            samples,
+            seed,
+        }
+    }
+
+    ///Every legal card of the player to move, the best one for the player comes first.
+    /// Empty if no deal fits to the view.
+    pub fn analyze_moves(&self, view: &PlayerView) -> Vec<MoveEvaluation> {
+        debug_assert_eq!(view.get_current_player(), view.player);
+        let knowledge = view.get_knowledge();
+        let maximizes = (view.player == view.declarer) != view.variant.is_null();
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut evaluations: Vec<MoveEvaluation> = Vec::new();
+        let mut solved = 0;
+        for _ in 0..self.samples {
+            //every consistent deal has the same probability
+            let Some(deal) = knowledge.sample_deal(&mut rng) else {
+                break;
+            };
+            let scores = solve_moves(view, knowledge, &deal);
+            let best = if maximizes {
+                scores.iter().map(|(_, score)| *score).max()
+            } else {
+                scores.iter().map(|(_, score)| *score).min()
+            };
+            for (card, score) in scores {
+                let is_best = Some(score) == best;
+                match evaluations.iter_mut().find(|evaluation| evaluation.card == card) {
+                    Some(evaluation) => {
+                        evaluation.average += score as f64;
+                        evaluation.best_count += is_best as usize;
+                    }
+                    None => evaluations.push(MoveEvaluation {
+                        card,
+                        average: score as f64,
+                        best_count: is_best as usize,
+                    }),
+                }
+            }
+            solved += 1;
+        }
+        for evaluation in &mut evaluations {
+            evaluation.average /= solved as f64;
+        }
+        if maximizes {
+            evaluations.sort_by(|a, b| b.average.total_cmp(&a.average));
+        } else {
+            evaluations.sort_by(|a, b| a.average.total_cmp(&b.average));
+        }
+        evaluations
+    }
+
+    ///The card with the best average score
+    pub fn best_move(&self, view: &PlayerView) -> Option<BitCard> {
+        self.analyze_moves(view).first().map(|evaluation| evaluation.card)
+    }
+}
+
+///Final score of the declarer after every legal card of the player to move in one deal
+pub(crate) fn solve_moves(view: &PlayerView, knowledge: &Knowledge, deal: &PossibleDeal) -> Vec<(BitCard, u8)> {
+    let remaining = deal.hands[0] | deal.hands[1] | deal.hands[2];
+    //the cards of the open trick still belong to the hands of the global state
+    let mut full_hands = deal.hands;
+    for (card, player) in &knowledge.trick {
+        full_hands[*player as usize] = full_hands[*player as usize] | *card;
+    }
+    let global_state = BitGlobal::new(full_hands[0], full_hands[1], full_hands[2], view.declarer, view.variant);
+    let trick: Vec<BitCard> = knowledge.trick.iter().map(|(card, _)| *card).collect();
+    let local_state = BitLocal::with_trick(remaining.0, view.player, &trick, &global_state)
+        .expect("sampled hands fit to the played cards");
+    let mut solver = MoreEnhancedSolver::new(global_state);
+    //the skat is added here, as the global state only knows it if no trick was played
+    let skat_points = if view.variant.is_null() { 0 } else { deal.skat.get_cards_points() };
+    let already_counted = solver.global_state.skat_points;
+    solver.analyze_moves(local_state).into_iter()
+        .map(|(card, score)| (card, score - already_counted + skat_points + knowledge.declarer_score))
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::dataset::read_dataset;
+    use crate::pimc::{Pimc, PlayerView};
+    use crate::solver::bitboard::{BitCard, BitCards};
+    use crate::solver::bitstates::{BitGlobal, BitLocal};
+    use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use crate::solver::{Player, Variant};
+
+    ///View of the player to move after the cards were played, the declarer is player one
+    pub(crate) fn get_view_to_move(hands: [BitCards; 3], starting_player: Player, variant: Variant, played_cards: &[BitCard]) -> PlayerView {
+        let all_cards = hands[0] | hands[1] | hands[2];
+        let mut views = [Player::One, Player::Two, Player::Three].map(|player| {
+            PlayerView::new(player, Player::One, starting_player, variant, hands[player as usize])
+                .with_hidden(BitCards(all_cards.0 & !hands[player as usize].0))
+        });
+        for card in played_cards {
+            for view in &mut views {
+                view.play(*card).unwrap();
+            }
+        }
+        let player = views[0].get_current_player();
+        views[player as usize].clone()
+    }
+
+    #[test]
+    fn pimc_deterministic() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(20) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            //the first trick and the next lead are played as in the principal variation
+            let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, Player::One, variant));
+            let variation = solver.principal_variation(BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state));
+            let view = get_view_to_move([p1, p2, p3], current_player, variant, &variation[..4]);
+            let player = view.player;
+            let pimc = Pimc::new(10, 42);
+            let evaluations = pimc.analyze_moves(&view);
+            assert_eq!(evaluations, pimc.analyze_moves(&view));
+            assert!(!evaluations.is_empty());
+            assert!(evaluations.iter().all(|evaluation| view.hand.0 & evaluation.card.0 != 0));
+            let maximizes = (player == Player::One) != variant.is_null();
+            assert!(evaluations.windows(2).all(|pair| (pair[0].average >= pair[1].average) == maximizes || pair[0].average == pair[1].average));
+        }
+    }
+}
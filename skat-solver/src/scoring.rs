@@ -0,0 +1,261 @@
+use crate::solver::bitboard::BitCards;
+use crate::solver::{GlobalState, Player, Variant};
+
+///Multiplier levels announced by the declarer, Schneider, Schwarz and Ouvert are only allowed in Hand games.
+/// For null games Hand and Ouvert are part of the variant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Announcement {
+    pub hand: bool,
+    pub schneider: bool,
+    pub schwarz: bool,
+    pub ouvert: bool,
+}
+
+///Result of a played or solved game from the view of the declarer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    ///card points including the skat
+    pub declarer_points: u8,
+    pub declarer_tricks: u8,
+    pub tricks: u8,
+}
+
+impl Outcome {
+    pub fn new(declarer_points: u8, declarer_tricks: u8, tricks: u8) -> Outcome {
+        Outcome {
+            declarer_points,
+            declarer_tricks,
+            tricks,
+        }
+    }
+
+    ///Solved result of a full null game, which counts the tricks of the declarer
+    pub fn from_null(declarer_tricks: u8) -> Outcome {
+        Outcome::new(0, declarer_tricks, 10)
+    }
+
+    fn is_schneider(&self) -> bool {
+        self.declarer_points >= 90 || self.declarer_points <= 30
+    }
+
+    fn is_schwarz(&self) -> bool {
+        self.declarer_tricks == 0 || self.declarer_tricks == self.tricks
+    }
+}
+
+///Top trumps held (with) or missing (against) in an unbroken sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Matadors {
+    With(u8),
+    Against(u8),
+}
+
+impl Matadors {
+    pub fn get_amount(&self) -> u8 {
+        match self {
+            Matadors::With(amount) | Matadors::Against(amount) => *amount
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub won: bool,
+    pub overbid: bool,
+    pub game_value: u16,
+    ///score of the declarer, lost games count twice
+    pub score: i16,
+}
+
+///Value of the declared variant, none for Ramsch which is never declared
+pub fn get_base_value(variant: &Variant) -> Option<u16> {
+    let value = match variant {
+        Variant::Grand => 24,
+        Variant::Clubs => 12,
+        Variant::Spades => 11,
+        Variant::Hearts => 10,
+        Variant::Diamonds => 9,
+        Variant::Null => 23,
+        Variant::NullHand => 35,
+        Variant::NullOuvert => 46,
+        Variant::NullOuvertHand => 59,
+        Variant::Ramsch => return None,
+    };
+    Some(value)
+}
+
+///Counts the matadors of the declarer, cards are the declarer's hand plus the skat.
+/// None for null games and Ramsch, whose values do not depend on trumps.
+pub fn get_matadors(cards: BitCards, variant: &Variant) -> Option<Matadors> {
+    if variant.is_null() || *variant == Variant::Ramsch {
+        return None;
+    }
+    let trumps = variant.get_binary_mask();
+    //the bit order of the trumps is the trump order
+    let mut position = 31;
+    let with = cards.0 & (1 << position) != 0;
+    let mut amount = 0;
+    while position >= 0 {
+        let card = 1 << position;
+        position -= 1;
+        if trumps & card == 0 {
+            continue;
+        }
+        if (cards.0 & card != 0) != with {
+            break;
+        }
+        amount += 1;
+    }
+    if with {
+        Some(Matadors::With(amount))
+    } else {
+        Some(Matadors::Against(amount))
+    }
+}
+
+pub(crate) fn get_declarer_cards(global_state: &GlobalState) -> BitCards {
+    let hand = match global_state.alone_player {
+        Player::One => global_state.player_cards.0,
+        Player::Two => global_state.player_cards.1,
+        Player::Three => global_state.player_cards.2,
+    };
+    hand | global_state.skat
+}
+
+///Calculates the game value and the score of the declarer, none for Ramsch
+pub fn calculate_game_value(global_state: &GlobalState, announcement: &Announcement, outcome: &Outcome, bid: u16) -> Option<GameResult> {
+    let variant = &global_state.variant;
+    let base_value = get_base_value(variant)?;
+    let (won, game_value) = if variant.is_null() {
+        (outcome.declarer_tricks == 0, base_value)
+    } else {
+        let schwarz_announced = announcement.schwarz || announcement.ouvert;
+        let schneider_announced = announcement.schneider || schwarz_announced;
+        let won = if schwarz_announced {
+            outcome.declarer_tricks == outcome.tricks
+        } else if schneider_announced {
+            outcome.declarer_points >= 90
+        } else {
+            outcome.declarer_points > 60
+        };
+        //game
+        let mut multiplier = 1 + get_matadors(get_declarer_cards(global_state), variant)?.get_amount() as u16;
+        for level in [
+            announcement.hand,
+            outcome.is_schneider() || schneider_announced,
+            schneider_announced,
+            outcome.is_schwarz() || schwarz_announced,
+            schwarz_announced,
+            announcement.ouvert,
+        ] {
+            if level {
+                multiplier += 1;
+            }
+        }
+        (won, multiplier * base_value)
+    };
+    if game_value < bid {
+        //the smallest multiple of the base value reaching the bid
+        let game_value = bid.div_ceil(base_value) * base_value;
+        return Some(GameResult {
+            won: false,
+            overbid: true,
+            game_value,
+            score: -2 * game_value as i16,
+        });
+    }
+    Some(GameResult {
+        won,
+        overbid: false,
+        game_value,
+        score: if won { game_value as i16 } else { -2 * game_value as i16 },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scoring::{Announcement, calculate_game_value, get_base_value, get_matadors, Matadors, Outcome};
+    use crate::solver::bitboard::{BitCards, HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_QUEEN, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_EIGHT, KARO_JACK, KARO_KING, KARO_NINE, KARO_QUEEN, KARO_SEVEN, KARO_TEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_NINE, KREUZ_QUEEN, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_EIGHT, PIQUS_JACK, PIQUS_KING, PIQUS_NINE, PIQUS_QUEEN, PIQUS_SEVEN, PIQUS_TEN};
+    use crate::solver::{GlobalState, Player, Variant};
+
+    fn create_global_state(alone_player: Player, variant: Variant) -> GlobalState {
+        let player_one = KREUZ_JACK | PIQUS_JACK | KREUZ_ASS | KREUZ_TEN | KREUZ_KING | HEARTS_ASS |
+            HEARTS_TEN | PIQUS_ASS | KARO_SEVEN | KARO_EIGHT;
+        let player_two = HEARTS_JACK | KREUZ_QUEEN | KREUZ_NINE | HEARTS_KING | HEARTS_QUEEN | HEARTS_NINE |
+            PIQUS_TEN | PIQUS_KING | KARO_ASS | KARO_TEN;
+        let player_three = KARO_JACK | KREUZ_EIGHT | KREUZ_SEVEN | HEARTS_EIGHT | HEARTS_SEVEN | PIQUS_QUEEN |
+            PIQUS_NINE | PIQUS_EIGHT | KARO_KING | KARO_QUEEN;
+        let skat = PIQUS_SEVEN | KARO_NINE;
+        GlobalState::new((player_one, player_two, player_three), skat, alone_player, variant)
+    }
+
+    #[test]
+    fn matadors() {
+        let all_trumps = KREUZ_JACK | PIQUS_JACK | HEARTS_JACK | KARO_JACK | KREUZ_ASS | KREUZ_TEN |
+            KREUZ_KING | KREUZ_QUEEN | KREUZ_NINE | KREUZ_EIGHT | KREUZ_SEVEN;
+        assert_eq!(get_matadors(all_trumps, &Variant::Clubs), Some(Matadors::With(11)));
+        assert_eq!(get_matadors(all_trumps, &Variant::Grand), Some(Matadors::With(4)));
+        assert_eq!(get_matadors(all_trumps, &Variant::Hearts), Some(Matadors::With(4)));
+        assert_eq!(get_matadors(KARO_JACK | HEARTS_ASS, &Variant::Hearts), Some(Matadors::Against(3)));
+        assert_eq!(get_matadors(BitCards(0), &Variant::Diamonds), Some(Matadors::Against(11)));
+        assert_eq!(get_matadors(all_trumps, &Variant::Null), None);
+        assert_eq!(get_matadors(all_trumps, &Variant::Ramsch), None);
+    }
+
+    #[test]
+    fn won_games() {
+        //with 2, game 3
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Grand), &Announcement::default(), &Outcome::new(70, 6, 10), 18).unwrap();
+        assert!(result.won);
+        assert_eq!(result.game_value, 72);
+        assert_eq!(result.score, 72);
+        //against 2, game 3, hand 4, schneider 5, announced 6
+        let announcement = Announcement { hand: true, schneider: true, ..Default::default() };
+        let result = calculate_game_value(&create_global_state(Player::Two, Variant::Spades), &announcement, &Outcome::new(95, 8, 10), 18).unwrap();
+        assert!(result.won);
+        assert_eq!(result.game_value, 66);
+        //with 2, game 3, schneider 4, schwarz 5
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Clubs), &Announcement::default(), &Outcome::new(120, 10, 10), 18).unwrap();
+        assert_eq!(result.game_value, 60);
+        let result = calculate_game_value(&create_global_state(Player::Three, Variant::NullOuvert), &Announcement::default(), &Outcome::from_null(0), 46).unwrap();
+        assert!(result.won);
+        assert_eq!(result.score, 46);
+    }
+
+    #[test]
+    fn lost_games() {
+        //with 2, game 3, schneider 4
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Grand), &Announcement::default(), &Outcome::new(25, 2, 10), 18).unwrap();
+        assert!(!result.won);
+        assert_eq!(result.game_value, 96);
+        assert_eq!(result.score, -192);
+        //announced schneider but did not reach it, is valued as schneider
+        let announcement = Announcement { hand: true, schneider: true, ..Default::default() };
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Grand), &announcement, &Outcome::new(80, 6, 10), 18).unwrap();
+        assert!(!result.won);
+        assert!(!result.overbid);
+        assert_eq!(result.game_value, 144);
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Null), &Announcement::default(), &Outcome::from_null(1), 23).unwrap();
+        assert!(!result.won);
+        assert_eq!(result.score, -46);
+    }
+
+    #[test]
+    fn overbid() {
+        //with 2, game 3 -> 27, bid 30
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Diamonds), &Announcement::default(), &Outcome::new(80, 7, 10), 30).unwrap();
+        assert!(!result.won);
+        assert!(result.overbid);
+        assert_eq!(result.game_value, 36);
+        assert_eq!(result.score, -72);
+        let result = calculate_game_value(&create_global_state(Player::One, Variant::Null), &Announcement::default(), &Outcome::from_null(0), 24).unwrap();
+        assert!(result.overbid);
+        assert_eq!(result.game_value, 46);
+    }
+
+    #[test]
+    fn ramsch_value() {
+        assert_eq!(get_base_value(&Variant::Ramsch), None);
+        assert_eq!(calculate_game_value(&create_global_state(Player::One, Variant::Ramsch), &Announcement::default(), &Outcome::new(40, 3, 10), 0), None);
+    }
+}
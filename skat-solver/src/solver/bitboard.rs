@@ -14,6 +14,11 @@ const PIQUS_TRUMPF_MASK: u32 = PIQUS_MASK | GRAND_MASK;
 const HEARTS_TRUMPF_MASK: u32 = HEARTS_MASK | GRAND_MASK;
 const KARO_TRUMPF_MASK: u32 = KARO_MASK | GRAND_MASK;
 
+pub(crate) const KREUZ_NULL_MASK: u32 = KREUZ_MASK | KREUZ_JACK.0;
+pub(crate) const PIQUS_NULL_MASK: u32 = PIQUS_MASK | PIQUS_JACK.0;
+pub(crate) const HEARTS_NULL_MASK: u32 = HEARTS_MASK | HEARTS_JACK.0;
+pub(crate) const KARO_NULL_MASK: u32 = KARO_MASK | KARO_JACK.0;
+
 const SEVEN_MASK: u32 = get_binary_mask_for_rank(1);
 const EIGHT_MASK: u32 = get_binary_mask_for_rank(2);
 const NINE_MASK: u32 = get_binary_mask_for_rank(3);
@@ -71,6 +76,25 @@ const fn get_binary_mask_for_colors(lower: u32, upper: u32) -> u32 {
     ((1 << (upper - lower)) - 1) << lower
 }
 
+///Position of every card in the null order: each suit gets 8 bits ordered 7-8-9-10-J-Q-K-A
+const NULL_ORDER: [u8; 32] = generate_null_order();
+
+const fn generate_null_order() -> [u8; 32] {
+    //7, 8, 9, Dame, König, 10, Ass
+    const RANKS: [u8; 7] = [0, 1, 2, 5, 6, 3, 7];
+    let mut order = [0; 32];
+    let mut i = 0;
+    while i < 28 {
+        order[i] = (i / 7) as u8 * 8 + RANKS[i % 7];
+        i += 1;
+    }
+    while i < 32 {
+        order[i] = (i - 28) as u8 * 8 + 4;
+        i += 1;
+    }
+    order
+}
+
 
 
 
@@ -119,7 +143,7 @@ impl BitAnd for BitCards {
 }
 
 impl BitCards {
-    pub(crate) fn get_cards_points(&self) -> u8 {
+    pub fn get_cards_points(&self) -> u8 {
         let mut result = 0;
         for card in *self {
             result += card.get_point();
@@ -169,7 +193,7 @@ impl BitCard {
         //CARDS_TO_INDEX[(self.0 % 37) as usize]
     }
 
-    pub(crate) fn get_human_representation(&self) -> String {
+    pub fn get_human_representation(&self) -> String {
         if self.0 == 0 {
             return "No card".to_string();
         }
@@ -219,7 +243,27 @@ impl BitCard {
         }
         KARO_MASK
     }
-    pub(crate) fn get_point(&self) -> u8 {
+
+    pub(crate) fn get_null_color_mask(&self) -> u32 {
+        if self.0 & KREUZ_NULL_MASK != 0 {
+            return KREUZ_NULL_MASK
+        }
+        else if self.0 & PIQUS_NULL_MASK != 0 {
+            return PIQUS_NULL_MASK
+        }
+        else if self.0 & HEARTS_NULL_MASK != 0 {
+            return HEARTS_NULL_MASK
+        }
+        KARO_NULL_MASK
+    }
+
+    ///Only comparable between cards of the same suit
+    #[inline(always)]
+    fn get_null_order(&self) -> u32 {
+        1 << NULL_ORDER[self.get_numerical_representation() as usize]
+    }
+
+    pub fn get_point(&self) -> u8 {
         if self.0 & SEVEN_OR_EIGHT_OR_NINE != 0 {
             return 0
         }
@@ -240,6 +284,12 @@ impl BitCard {
     }
 
     pub fn greater_than(&self, other: BitCard, variant: &Variant) -> bool {
+        if variant.is_null() {
+            if other.0 & self.get_null_color_mask() == 0 {
+                return true;
+            }
+            return self.get_null_order() > other.get_null_order();
+        }
         let mask = variant.get_binary_mask();
         let card1 = self.0 & mask;
         let card2 = other.0 & mask;
@@ -253,8 +303,13 @@ impl BitCard {
 
 }
 
+///Returns the winning card and the value of the trick.
+///In null games a trick is worth one, as the declarer is only allowed to lose tricks.
 #[inline(always)]
-pub(crate) fn calculate_who_won_better(first : BitCard, second: BitCard, third: BitCard, variant: &Variant) -> (BitCard, u8) {
+pub fn calculate_who_won_better(first : BitCard, second: BitCard, third: BitCard, variant: &Variant) -> (BitCard, u8) {
+    if variant.is_null() {
+        return (calculate_who_won_null(first, second, third), 1);
+    }
     let points = first.get_point() + second.get_point() + third.get_point();
     let mask = variant.get_binary_mask();
     let first_tr = first.0 & mask;
@@ -268,13 +323,25 @@ pub(crate) fn calculate_who_won_better(first : BitCard, second: BitCard, third:
     (BitCard(max(max(first_tr, second_tr), third_tr)), points)
 }
 
+#[inline(always)]
+fn calculate_who_won_null(first: BitCard, second: BitCard, third: BitCard) -> BitCard {
+    let color = first.get_null_color_mask();
+    let mut winner = first;
+    for card in [second, third] {
+        if card.0 & color != 0 && card.get_null_order() > winner.get_null_order() {
+            winner = card;
+        }
+    }
+    winner
+}
+
 
 
 
 
 #[cfg(test)]
 mod tests {
-    use crate::solver::bitboard::{calculate_who_won_better, HEARTS_EIGHT, HEARTS_QUEEN, HEARTS_TEN, KARO_EIGHT, KARO_NINE, KARO_SEVEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_KING, KREUZ_TEN, PIQUS_ASS, PIQUS_KING, PIQUS_TEN};
+    use crate::solver::bitboard::{calculate_who_won_better, HEARTS_EIGHT, HEARTS_JACK, HEARTS_QUEEN, HEARTS_TEN, KARO_EIGHT, KARO_NINE, KARO_SEVEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_TEN, PIQUS_ASS, PIQUS_JACK, PIQUS_KING, PIQUS_TEN};
     use crate::solver::{Variant};
 
     #[test]
@@ -326,4 +393,28 @@ mod tests {
         assert_eq!(result.1, 25);
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cards_in_null() {
+        let null_variants = [Variant::Null, Variant::NullHand, Variant::NullOuvert, Variant::NullOuvertHand];
+        //ten is below jack, queen and king
+        for variant in &null_variants {
+            let result = calculate_who_won_better(HEARTS_TEN, HEARTS_QUEEN, HEARTS_EIGHT, variant);
+            assert_eq!(result.0, HEARTS_QUEEN);
+            assert_eq!(result.1, 1);
+            let result = calculate_who_won_better(HEARTS_TEN, HEARTS_JACK, HEARTS_EIGHT, variant);
+            assert_eq!(result.0, HEARTS_JACK);
+            let result = calculate_who_won_better(HEARTS_JACK, HEARTS_QUEEN, HEARTS_TEN, variant);
+            assert_eq!(result.0, HEARTS_QUEEN);
+        }
+        //jacks are no trumps and belong to their suit
+        let result = calculate_who_won_better(KREUZ_EIGHT, KREUZ_JACK, PIQUS_JACK, &Variant::Null);
+        assert_eq!(result.0, KREUZ_JACK);
+        let result = calculate_who_won_better(KARO_SEVEN, KREUZ_JACK, PIQUS_ASS, &Variant::Null);
+        assert_eq!(result.0, KARO_SEVEN);
+        assert!(KREUZ_KING.greater_than(KREUZ_TEN, &Variant::Null));
+        assert!(!KREUZ_TEN.greater_than(KREUZ_JACK, &Variant::Null));
+        assert!(!KREUZ_TEN.greater_than(KREUZ_JACK, &Variant::Grand));
+        assert!(KARO_SEVEN.greater_than(KREUZ_ASS, &Variant::Null));
+    }
+}
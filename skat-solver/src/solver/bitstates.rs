@@ -1,10 +1,11 @@
 use arrayvec::ArrayVec;
-use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better, GRAND_MASK, HEARTS_MASK, KARO_MASK, KREUZ_MASK, PIQUS_MASK, SEVEN_OR_EIGHT_OR_NINE};
-use crate::solver::{Player, Variant};
+use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better, GRAND_MASK, HEARTS_MASK, HEARTS_NULL_MASK, KARO_MASK, KARO_NULL_MASK, KREUZ_MASK, KREUZ_NULL_MASK, PIQUS_MASK, PIQUS_NULL_MASK, SEVEN_OR_EIGHT_OR_NINE};
+use crate::solver::{Player, TrickError, validate_trick, Variant};
 
 ///
 /// Represents the global state of a gamge
-/// alone_player is always player_one -> local / and gloabl state has to be adjusted if that is not the case
+/// internally the declarer is always player one, the seats are rotated in new
+/// and every player given to or returned by the states is in the original seats
 pub struct BitGlobal {
     player_one: BitCards,
     player_two: BitCards,
@@ -13,15 +14,21 @@ pub struct BitGlobal {
     first_card_skat: u32,
     second_card_skat: u32,
     color_masks: [u32; 5],
+    //cards which can not change the score after a finished trick, none if tricks are counted
+    skippable_cards: u32,
+    //every trick of player one is worth one instead of its card points
+    pub(crate) count_tricks: bool,
     //not relevant
     pub skat_points: u8,
     //relevant *5
-    variant: Variant
+    variant: Variant,
+    //original seat of the declarer
+    declarer: Player,
 }
 
 const fn generate_color_mask(variant: Variant) -> [u32; 5] {
     match variant {
-        Variant::Grand => {
+        Variant::Grand | Variant::Ramsch => {
             [GRAND_MASK, KREUZ_MASK, PIQUS_MASK, HEARTS_MASK, KARO_MASK]
         }
         Variant::Clubs => {
@@ -36,12 +43,21 @@ const fn generate_color_mask(variant: Variant) -> [u32; 5] {
         Variant::Diamonds => {
             [KREUZ_MASK, PIQUS_MASK, HEARTS_MASK, KARO_MASK | GRAND_MASK, 0]
         }
+        Variant::Null | Variant::NullHand | Variant::NullOuvert | Variant::NullOuvertHand => {
+            [KREUZ_NULL_MASK, PIQUS_NULL_MASK, HEARTS_NULL_MASK, KARO_NULL_MASK, 0]
+        }
     }
 
 }
 
 impl BitGlobal {
-    pub fn new(pl_one: BitCards, pl_two: BitCards, pl_three: BitCards, variant: Variant) -> BitGlobal {
+    pub fn new(pl_one: BitCards, pl_two: BitCards, pl_three: BitCards, declarer: Player, variant: Variant) -> BitGlobal {
+        //the declarer and the players after him become player one, two and three
+        let (pl_one, pl_two, pl_three) = match declarer {
+            Player::One => (pl_one, pl_two, pl_three),
+            Player::Two => (pl_two, pl_three, pl_one),
+            Player::Three => (pl_three, pl_one, pl_two),
+        };
         let mut skat = BitCards(!(pl_one | pl_two | pl_three).0);
         let first_card = skat.get_next_card_in_binary().0;
         let second_card;
@@ -56,6 +72,11 @@ impl BitGlobal {
             skat_points = skat.get_cards_points();
         }
         let color_masks = generate_color_mask(variant);
+        let (skat_points, skippable_cards) = if variant.is_null() {
+            (0, 0)
+        } else {
+            (skat_points, SEVEN_OR_EIGHT_OR_NINE)
+        };
         BitGlobal {
             player_one: pl_one,
             player_two: pl_two,
@@ -64,19 +85,50 @@ impl BitGlobal {
             first_card_skat: first_card,
             second_card_skat: second_card,
             color_masks,
+            skippable_cards,
+            count_tricks: false,
             skat_points,
             variant,
+            declarer,
         }
 
     }
+
+    pub fn get_declarer(&self) -> Player {
+        self.declarer
+    }
+
+    ///Seat of the rotated states for a player of the original seats
+    #[inline(always)]
+    fn to_internal(&self, player: Player) -> Player {
+        Player::from((player as u8 + 3 - self.declarer as u8) % 3)
+    }
+
+    ///Original seat of a player of the rotated states
+    #[inline(always)]
+    fn to_original(&self, player: Player) -> Player {
+        Player::from((player as u8 + self.declarer as u8) % 3)
+    }
 }
 
 
 impl BitGlobal {
+    pub(crate) fn set_count_tricks(&mut self, count_tricks: bool) {
+        self.count_tricks = count_tricks;
+        self.skippable_cards = if count_tricks || self.variant.is_null() {
+            0
+        } else {
+            SEVEN_OR_EIGHT_OR_NINE
+        };
+    }
+
     #[inline(always)]
     fn calculate_winner_state(&self, winner_move: BitCard, points: u8, old_state: u32) -> (u32, u8) {
         let new_state = old_state & (!self.skat.0);
         if winner_move.0 & self.player_one.0 != 0 {
+            if self.count_tricks {
+                return (new_state, 1)
+            }
             return (new_state, points)
         }
         if winner_move.0 & self.player_two.0 !=0 {
@@ -101,9 +153,11 @@ pub struct BitLocal {
 }
 
 impl BitLocal {
-    pub fn new(all_cards: u32, current_player: Player, skat: BitCards) -> BitLocal {
+    ///current_player is given in the original seats of the global state
+    pub fn new(all_cards: u32, current_player: Player, global_state: &BitGlobal) -> BitLocal {
+        let skat = global_state.skat;
         let next = skat.get_next_card_in_binary();
-        match current_player {
+        match global_state.to_internal(current_player) {
             Player::One => {
                 BitLocal {
                     state: all_cards,
@@ -127,6 +181,23 @@ impl BitLocal {
             }
         }
     }
+
+    ///
+    /// Position inside a trick, played_cards are the cards on the table in the order they were played.
+    /// all_cards are the cards still in the hands, the hands of the global state still contain the played cards.
+    pub fn with_trick(all_cards: u32, current_player: Player, played_cards: &[BitCard], global_state: &BitGlobal) -> Result<BitLocal, TrickError> {
+        let hands = (global_state.player_one, global_state.player_two, global_state.player_three);
+        let internal_player = global_state.to_internal(current_player);
+        validate_trick(hands, BitCards(all_cards), internal_player, played_cards, &global_state.variant)?;
+        let mut local_state = BitLocal::new(all_cards, current_player, global_state);
+        if let Some(first) = played_cards.first() {
+            local_state.current_played_cards = (*first, played_cards.get(1).copied().unwrap_or(BitCard(0)));
+            local_state.current_suit = global_state.color_masks.iter().copied()
+                .find(|mask| mask & first.0 != 0)
+                .unwrap_or_default();
+        }
+        Ok(local_state)
+    }
 }
 
 
@@ -136,24 +207,48 @@ impl BitLocal {
         BitCards(self.state & !global_state.skat.0).get_cards_points()
     }
 
+    ///In null games the score counts the tricks of player one, so the defenders maximize it
     #[inline(always)]
     pub fn is_max_node(&self, global_state: &BitGlobal) -> bool {
-        self.state & global_state.skat.0 == 0
+        (self.state & global_state.skat.0 == 0) != global_state.variant.is_null()
+    }
+    ///The player to move in the original seats
+    pub fn get_current_player(&self, global_state: &BitGlobal) -> Player {
+        let player = self.state & global_state.skat.0;
+        let internal = if player == 0 {
+            Player::One
+        } else if player & global_state.second_card_skat == 0 {
+            Player::Two
+        } else {
+            Player::Three
+        };
+        global_state.to_original(internal)
     }
     #[inline(always)]
     pub fn get_hash(&self) -> u32 {
         self.state
     }
+    ///Amount of cards left in the hands, used to keep deep entries in the table
+    #[inline(always)]
+    pub(crate) fn get_depth(&self, global_state: &BitGlobal) -> u8 {
+        (self.state & !global_state.skat.0).count_ones() as u8
+    }
     #[inline(always)]
     pub fn is_full_node(&self) -> bool {
         self.current_suit == 0
     }
 
+    ///The card played to get from this state to the next state
+    #[inline(always)]
+    pub(crate) fn get_played_card(&self, next_state: &BitLocal, global_state: &BitGlobal) -> BitCard {
+        BitCard(self.state & !next_state.state & !global_state.skat.0)
+    }
+
     #[inline(always)]
-    pub fn is_terminal(&self, skat: BitCards) -> bool {
-        let mut cards = self.state & (!skat.0);
+    pub fn is_terminal(&self, global_state: &BitGlobal) -> bool {
+        let mut cards = self.state & (!global_state.skat.0);
         if self.is_full_node() {
-            cards &= !SEVEN_OR_EIGHT_OR_NINE;
+            cards &= !global_state.skippable_cards;
         }
         cards == 0
     }
@@ -281,7 +376,7 @@ impl BitLocal {
             }
             color_amount[i] = (c, *color_mask);
         }
-        color_amount.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        color_amount.sort_unstable_by_key(|a| a.0);
         for (amount, color) in color_amount {
             if amount == u8::MAX { break; }
             let mut avaialble_in_color = first & color;
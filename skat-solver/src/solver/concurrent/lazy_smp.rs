@@ -1,46 +1,308 @@
 use std::cmp::{max, min};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
 use fxhash::FxHashMap;
+use crate::solver::bitboard::BitCard;
 use crate::solver::bitstates::{BitGlobal, BitLocal};
+use crate::solver::{Deal, Solver};
 use crate::solver::synchronus::ab_tt::Bounds;
 use crate::solver::synchronus::ab_tt::Bounds::{LowerBound, UpperBound, Valid};
+use crate::solver::synchronus::limits::{BoundedResult, Budget, SearchLimits};
+use crate::solver::synchronus::stats::SearchStats;
+use crate::solver::synchronus::trans_table::{FixedTable, TableStats, TranspositionTable};
 
-pub struct LazyMoreEnhancedSolver {
+const STRIPES: usize = 64;
+
+///
+/// Transposition table shared by all threads, keyed by BitLocal::get_hash.
+/// The entries are split over several locked tables, so threads rarely wait for each other.
+pub struct SharedTable<T: TranspositionTable<u32> = FxHashMap<u32, (i8, Bounds)>> {
+    stripes: Vec<Mutex<T>>,
+}
+
+impl<T: TranspositionTable<u32> + Default> Default for SharedTable<T> {
+    fn default() -> Self {
+        Self::from_fn(T::default)
+    }
+}
+
+impl SharedTable<FixedTable> {
+    ///Fixed size stripes, which together use at most the given amount of bytes
+    pub fn with_memory(bytes: usize) -> SharedTable<FixedTable> {
+        Self::from_fn(|| FixedTable::with_memory(bytes / STRIPES))
+    }
+
+    pub fn get_stats(&self) -> TableStats {
+        let mut stats = TableStats::default();
+        for stripe in &self.stripes {
+            let stripe = stripe.lock().unwrap().get_stats();
+            stats.probes += stripe.probes;
+            stats.hits += stripe.hits;
+            stats.collisions += stripe.collisions;
+            stats.stores += stripe.stores;
+            stats.replacements += stripe.replacements;
+        }
+        stats
+    }
+}
+
+impl<T: TranspositionTable<u32>> SharedTable<T> {
+    pub fn from_fn(create_stripe: impl FnMut() -> T) -> SharedTable<T> {
+        Self {
+            stripes: std::iter::repeat_with(create_stripe).take(STRIPES).map(Mutex::new).collect(),
+        }
+    }
+
+    #[inline(always)]
+    fn get_stripe(&self, hash: u32) -> &Mutex<T> {
+        //the low bits are single cards, so the hash is mixed first
+        &self.stripes[(hash.wrapping_mul(0x9E37_79B9) >> 26) as usize % STRIPES]
+    }
+
+    pub fn get(&self, hash: u32) -> Option<(i8, Bounds)> {
+        self.get_stripe(hash).lock().unwrap().get(hash)
+    }
+
+    pub fn put(&self, hash: u32, score: i8, bound: Bounds, depth: u8) {
+        self.get_stripe(hash).lock().unwrap().put(hash, score, bound, depth);
+    }
+
+    pub fn len(&self) -> usize {
+        self.stripes.iter().map(|stripe| stripe.lock().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        for stripe in &self.stripes {
+            stripe.lock().unwrap().clear();
+        }
+    }
+}
+
+fn get_threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+///
+/// Lazy SMP: every thread searches the same root with its own move order and
+/// shares its results through the table. The first thread to finish gives the result,
+/// the others stop. As all entries are exact bounds the result is the same as MoreEnhancedSolver.
+pub struct LazyMoreEnhancedSolver<T: TranspositionTable<u32> + Send = FxHashMap<u32, (i8, Bounds)>> {
     pub global_state: BitGlobal,
-    pub look_up_table: FxHashMap<u32, (i8, Bounds)>,
+    pub look_up_table: SharedTable<T>,
+    threads: usize,
+    stats: SearchStats,
 }
 
 impl LazyMoreEnhancedSolver {
+    ///Uses one thread per available core
     pub fn new(global_state: BitGlobal) -> LazyMoreEnhancedSolver {
+        Self::with_threads(global_state, get_threads())
+    }
+
+    pub fn with_threads(global_state: BitGlobal, threads: usize) -> LazyMoreEnhancedSolver {
+        Self::with_table(global_state, threads, Default::default())
+    }
+}
+
+impl<T: TranspositionTable<u32> + Send> LazyMoreEnhancedSolver<T> {
+    pub fn with_table(global_state: BitGlobal, threads: usize, look_up_table: SharedTable<T>) -> LazyMoreEnhancedSolver<T> {
+        Self {
+            global_state,
+            look_up_table,
+            threads: max(threads, 1),
+            stats: Default::default(),
+        }
+    }
+
+    pub fn solve(&mut self, local_state: BitLocal) -> u8 {
+        self.get_value(local_state) as u8 + self.global_state.skat_points
+    }
+
+    ///Solves the state and returns the statistics of all threads, which are only collected with the `stats` feature
+    pub fn solve_with_stats(&mut self, local_state: BitLocal) -> (u8, SearchStats) {
+        self.stats = Default::default();
+        let result = self.solve(local_state);
+        (result, self.stats)
+    }
+
+    ///Solves until one of the limits is hit, the nodes are limited per thread.
+    /// The bounds of all threads are combined.
+    pub fn solve_limited(&mut self, local_state: BitLocal, limits: &SearchLimits) -> BoundedResult {
+        let (lower_bound, upper_bound) = self.get_bounds(local_state, limits);
+        BoundedResult::new(lower_bound, upper_bound, self.global_state.skat_points, lower_bound != upper_bound)
+    }
+
+    ///The card with the best score for the player to move
+    pub fn best_move(&mut self, local_state: BitLocal) -> Option<BitCard> {
+        let value = self.get_value(local_state);
+        self.get_best_child(local_state, value).map(|(next_state, _)| local_state.get_played_card(&next_state, &self.global_state))
+    }
+
+    ///The cards played until the end of the game if both sides play optimal
+    pub fn principal_variation(&mut self, local_state: BitLocal) -> Vec<BitCard> {
+        let mut value = self.get_value(local_state);
+        let mut current_state = local_state;
+        let mut variation = Vec::new();
+        while let Some((next_state, next_value)) = self.get_best_child(current_state, value) {
+            variation.push(current_state.get_played_card(&next_state, &self.global_state));
+            current_state = next_state;
+            value = next_value;
+        }
+        variation
+    }
+
+    ///Finds the first successor keeping the exact value of the state
+    fn get_best_child(&mut self, local_state: BitLocal, value: i8) -> Option<(BitLocal, i8)> {
+        let is_max = local_state.is_max_node(&self.global_state);
+        for (next_state, achieved_points) in local_state.get_next_states(&self.global_state) {
+            let next_value = value - achieved_points as i8;
+            if is_max {
+                if self.ab_tt(next_state, next_value - 1, next_value) >= next_value {
+                    return Some((next_state, next_value));
+                }
+            } else if self.ab_tt(next_state, next_value, next_value + 1) <= next_value {
+                return Some((next_state, next_value));
+            }
+        }
+        None
+    }
+
+    ///Exact score of the state without the skat, searched by all threads
+    fn get_value(&mut self, local_state: BitLocal) -> i8 {
+        self.get_bounds(local_state, &SearchLimits::default()).0
+    }
+
+    ///Proven lower and upper bound of all threads, equal unless a limit was hit
+    fn get_bounds(&mut self, local_state: BitLocal, limits: &SearchLimits) -> (i8, i8) {
+        let stop = AtomicBool::new(false);
+        let bounds = Mutex::new((0, 120));
+        let stats = Mutex::new(SearchStats::default());
+        thread::scope(|scope| {
+            for id in 1..self.threads {
+                let mut worker = LazyWorker::new(&self.global_state, &self.look_up_table, id, &stop, limits);
+                let bounds = &bounds;
+                let stats = &stats;
+                scope.spawn(move || worker.finish(local_state, bounds, stats));
+            }
+            LazyWorker::new(&self.global_state, &self.look_up_table, 0, &stop, limits).finish(local_state, &bounds, &stats);
+        });
+        self.stats += stats.into_inner().unwrap();
+        bounds.into_inner().unwrap()
+    }
+
+    ///Single threaded search on the shared table
+    pub fn ab_tt(&mut self, local_state: BitLocal, agoof: i8, bgoof: i8) -> i8 {
+        let stop = AtomicBool::new(false);
+        LazyWorker::new(&self.global_state, &self.look_up_table, 0, &stop, &SearchLimits::default()).ab_tt(local_state, agoof, bgoof)
+    }
+}
+
+///
+/// One search thread, every worker but the first one rotates the moves at full nodes.
+/// The offset comes from the seed of the worker and the position, so workers differ even with few moves.
+struct LazyWorker<'a, T: TranspositionTable<u32>> {
+    global_state: &'a BitGlobal,
+    look_up_table: &'a SharedTable<T>,
+    id: usize,
+    seed: u32,
+    stop: &'a AtomicBool,
+    budget: Budget,
+    stats: SearchStats,
+}
+
+impl<'a, T: TranspositionTable<u32>> LazyWorker<'a, T> {
+    fn new(global_state: &'a BitGlobal, look_up_table: &'a SharedTable<T>, id: usize, stop: &'a AtomicBool, limits: &SearchLimits) -> LazyWorker<'a, T> {
         Self {
             global_state,
-            look_up_table: Default::default(),
+            look_up_table,
+            id,
+            seed: (id as u32).wrapping_mul(0x9E37_79B9),
+            stop,
+            budget: Budget::new(limits),
+            stats: Default::default(),
+        }
+    }
+
+    #[inline(always)]
+    fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    ///Narrows the shared bounds, the first worker to finish stops all others
+    fn finish(&mut self, local_state: BitLocal, bounds: &Mutex<(i8, i8)>, stats: &Mutex<SearchStats>) {
+        let (lower_bound, upper_bound) = self.get_bounds(local_state);
+        *stats.lock().unwrap() += self.stats;
+        let mut bounds = bounds.lock().unwrap();
+        bounds.0 = max(bounds.0, lower_bound);
+        bounds.1 = min(bounds.1, upper_bound);
+        if lower_bound == upper_bound {
+            self.stop.store(true, Ordering::Relaxed);
         }
     }
 
     #[inline]
-    fn try_insert(&mut self, local_state: &BitLocal, score: i8, bound: Bounds) {
+    fn try_insert(&self, local_state: &BitLocal, score: i8, bound: Bounds) {
         if local_state.is_full_node() {
-            self.look_up_table.insert(local_state.get_hash(), (score, bound));
+            self.insert(local_state, score, bound);
         }
     }
+
     #[inline(always)]
-    fn insert(&mut self, pos: u32, score: i8, bound: Bounds) {
-        self.look_up_table.insert(pos, (score, bound));
+    fn insert(&self, local_state: &BitLocal, score: i8, bound: Bounds) {
+        self.look_up_table.put(local_state.get_hash(), score, bound, local_state.get_depth(self.global_state));
     }
-    pub fn solve(&mut self, local_state: BitLocal) -> u8 {
-        self.ab_tt(local_state, 0, 120) as u8 + self.global_state.skat_points
+
+    ///Same null window loop as MoreEnhancedSolver, stops early if another worker was faster
+    fn get_bounds(&mut self, local_state: BitLocal) -> (i8, i8) {
+        let mut min: i8 = 0;
+        let mut max = 120;
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let r = self.ab_tt(local_state, med, med + 1);
+            self.stats.add_mtd_iteration();
+            self.stats.update_table_size(|| self.look_up_table.len());
+            if self.is_stopped() {
+                break;
+            }
+            if r <= med {
+                max = r;
+            } else {
+                min = r;
+            }
+        }
+        (min, max)
     }
 
-    pub fn ab_tt(&mut self, local_state: BitLocal, agoof: i8, bgoof: i8) -> i8 {
-        if local_state.is_terminal(self.global_state.skat) {
+    ///Results of a stopped search are wrong, so they are neither stored nor used
+    fn ab_tt(&mut self, local_state: BitLocal, agoof: i8, bgoof: i8) -> i8 {
+        self.stats.add_node();
+        if self.budget.visit() {
+            //a limit of one worker stops all of them
+            self.stop.store(true, Ordering::Relaxed);
+            return 0;
+        }
+        if local_state.is_terminal(self.global_state) {
             return 0;
         }
         let mut new_alpha = agoof;
         let mut new_beta = bgoof;
         if local_state.is_full_node() {
-            if let Some(result) = self.look_up_table.get(&local_state.get_hash()) {
+            let entry = self.look_up_table.get(local_state.get_hash());
+            self.stats.add_probe(entry.map(|entry| entry.1));
+            if let Some(result) = entry {
                 match result.1 {
                     Valid => {
+                        self.stats.add_tt_cutoff(Valid);
                         return result.0
                     }
                     LowerBound => {
@@ -51,120 +313,168 @@ impl LazyMoreEnhancedSolver {
                     }
                 }
                 if new_alpha >= new_beta {
+                    self.stats.add_tt_cutoff(result.1);
                     return result.0
                 }
             }
         }
 
-
-        if local_state.is_max_node(&self.global_state) {
-            for (next_state, achieved_points) in local_state.get_next_states(&self.global_state) {
-                let t_q = achieved_points as i8;
-                let succ_val = t_q + self.ab_tt(next_state, new_alpha - t_q, new_beta - t_q);
+        let mut next_states = local_state.get_next_states(self.global_state);
+        if self.id != 0 && local_state.is_full_node() {
+            let len = next_states.len();
+            next_states.rotate_left(get_move_offset(self.seed, local_state.get_hash(), len));
+        }
+        let is_max = local_state.is_max_node(self.global_state);
+        for (index, (next_state, achieved_points)) in next_states.into_iter().enumerate() {
+            let t_q = achieved_points as i8;
+            let succ_val = t_q + self.ab_tt(next_state, new_alpha - t_q, new_beta - t_q);
+            if self.is_stopped() {
+                return 0;
+            }
+            if is_max {
                 new_alpha = max(new_alpha, succ_val);
                 if new_alpha >= new_beta {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_alpha, LowerBound);
                     return new_alpha;
                 }
-            }
-            if !local_state.is_full_node() {
-                return new_alpha;
-            }
-            if new_alpha != agoof {
-                self.insert(local_state.get_hash(), new_alpha, Valid);
             } else {
-                self.insert(local_state.get_hash(), new_alpha, UpperBound);
-            }
-
-            return new_alpha
-        }
-        for (next_state, achieved_points) in local_state.get_next_states(&self.global_state) {
-            let t_q = achieved_points as i8;
-            let succ_val = t_q + self.ab_tt(next_state, new_alpha - t_q, new_beta - t_q);
-            new_beta = min(new_beta, succ_val);
-            if new_beta <= new_alpha {
-                self.try_insert(&local_state, new_beta, UpperBound);
-                return new_beta;
+                new_beta = min(new_beta, succ_val);
+                if new_beta <= new_alpha {
+                    self.stats.add_cutoff(index);
+                    self.try_insert(&local_state, new_beta, UpperBound);
+                    return new_beta;
+                }
             }
         }
+
+        let result = if is_max {
+            new_alpha
+        } else {
+            new_beta
+        };
         if !local_state.is_full_node() {
-            return new_beta;
+            return result;
         }
-        if new_beta != bgoof {
-            self.insert(local_state.get_hash(), new_beta, Valid);
+        if is_max {
+            if new_alpha != agoof {
+                self.insert(&local_state, result, Valid);
+            } else {
+                self.insert(&local_state, result, UpperBound);
+            }
+        } else if result != bgoof {
+            self.insert(&local_state, result, Valid);
         } else {
-            self.insert(local_state.get_hash(), new_beta, LowerBound);
+            self.insert(&local_state, result, LowerBound);
         }
-        new_beta
+        result
+    }
+}
+
+///Rotation of the moves of a worker at a position
+fn get_move_offset(seed: u32, hash: u32, moves: usize) -> usize {
+    ((seed ^ hash).wrapping_mul(0x9E37_79B9) >> 16) as usize % moves
+}
+
+impl<T: TranspositionTable<u32> + Send + Default> Solver for LazyMoreEnhancedSolver<T> {
+    fn from_deal(deal: &Deal) -> Self {
+        Self::with_table(deal.get_bit_global(), get_threads(), SharedTable::default())
+    }
+
+    fn solve_deal(&mut self, deal: &Deal) -> u8 {
+        self.solve(BitLocal::new(deal.get_all_cards().0, deal.current_player, &self.global_state))
+    }
+
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard> {
+        self.best_move(BitLocal::new(deal.get_all_cards().0, deal.current_player, &self.global_state))
+    }
+
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard> {
+        self.principal_variation(BitLocal::new(deal.get_all_cards().0, deal.current_player, &self.global_state))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-    use crate::solver::bitboard::{BitCards};
-    use crate::solver::{Player, Variant};
+    use crate::solver::tests::{check_limits_file, check_stats_file, check_variation_file, run_data_file, run_data_file_with};
+    use crate::solver::concurrent::lazy_smp::SharedTable;
+    use crate::dataset::read_dataset;
+    use crate::solver::Player;
     use crate::solver::bitstates::{BitGlobal, BitLocal};
-    use crate::solver::concurrent::lazy_smp::LazyMoreEnhancedSolver;
+    use crate::solver::concurrent::lazy_smp::{get_move_offset, LazyMoreEnhancedSolver};
 
 
-    fn run_test(line: &str) -> Result<(), (u8, u8)> {
-        let data: Vec<&str> = line.split(',').collect();
-        let p1 = BitCards(data[0].parse::<u32>().unwrap());
-        let p2 = BitCards(data[1].parse::<u32>().unwrap());
-        let p3 = BitCards(data[2].parse::<u32>().unwrap());
-        let current_player: Player = Player::from(data[4].parse::<u8>().unwrap());
-        let variant: Variant = Variant::from(data[5].parse::<u8>().unwrap());
-        let score = data[6].parse::<u8>().unwrap();
-        let global_state = BitGlobal::new(p1, p2, p3, variant);
-        let local_state = BitLocal::new((p1 | p2 | p3).0, current_player, global_state.skat);
-        let mut solver = LazyMoreEnhancedSolver::new(global_state);
-        let result = solver.solve(local_state);
-        assert!((0..=120).contains(&result));
-        if result == score {
-            return Ok(());
+
+    #[test]
+    fn lazy_opt2_move_offsets() {
+        let seeds: Vec<u32> = (1..8).map(|id: u32| id.wrapping_mul(0x9E37_79B9)).collect();
+        for moves in 2..5 {
+            let orders: Vec<Vec<usize>> = seeds.iter()
+                .map(|seed| (0..64).map(|hash| get_move_offset(*seed, hash << 10, moves)).collect())
+                .collect();
+            //no two workers search all positions in the same order, every rotation is used
+            for (i, order) in orders.iter().enumerate() {
+                assert!(orders[i + 1..].iter().all(|other| other != order));
+                assert!((0..moves).all(|offset| order.contains(&offset)));
+            }
         }
-        Err((result, score))
     }
 
     #[test]
-    #[ignore]
     fn lazy_opt2_full() {
-        let input = fs::read_to_string("data/full_game.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<LazyMoreEnhancedSolver>("data/full_game.txt");
     }
 
     #[test]
     #[ignore]
     fn lazy_opt2_seven() {
-        let input = fs::read_to_string("data/seven_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<LazyMoreEnhancedSolver>("data/seven_cards.txt");
     }
 
     #[test]
     fn lazy_opt2_six() {
-        let input = fs::read_to_string("data/six_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
+        run_data_file::<LazyMoreEnhancedSolver>("data/six_cards.txt");
+    }
 
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
+    #[test]
+    fn lazy_opt2_threads() {
+        for position in read_dataset("data/six_cards.txt").unwrap() {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let score = position.result;
+            let global_state = BitGlobal::new(p1, p2, p3, Player::One, variant);
+            let local_state = BitLocal::new((p1 | p2 | p3).0, current_player, &global_state);
+            let mut solver = LazyMoreEnhancedSolver::with_threads(global_state, 4);
+            assert_eq!(solver.solve(local_state), score);
         }
-        assert_eq!(successes, len);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn lazy_opt2_principal_variation() {
+        check_variation_file::<LazyMoreEnhancedSolver>("data/four_cards.txt", usize::MAX);
+    }
+
+    #[test]
+    fn lazy_opt2_fixed_table() {
+        //a small table has to replace entries, the results stay exact
+        run_data_file_with("data/six_cards.txt", |deal| LazyMoreEnhancedSolver::with_table(deal.get_bit_global(), 2, SharedTable::with_memory(1 << 16)));
+    }
+
+    #[test]
+    fn lazy_opt2_limits() {
+        check_limits_file("data/full_game.txt", 3, |deal| LazyMoreEnhancedSolver::with_threads(deal.get_bit_global(), 2), |solver, deal, limits| {
+            let local_state = BitLocal::new(deal.get_all_cards().0, deal.current_player, &solver.global_state);
+            solver.solve_limited(local_state, limits)
+        });
+    }
+
+    #[test]
+    fn lazy_opt2_stats() {
+        check_stats_file("data/six_cards.txt", 50, true, |deal| {
+            let global_state = deal.get_bit_global();
+            let local_state = BitLocal::new(deal.get_all_cards().0, deal.current_player, &global_state);
+            LazyMoreEnhancedSolver::with_threads(global_state, 2).solve_with_stats(local_state)
+        });
+    }
+}
@@ -1,5 +1,9 @@
 use crate::solver::bitboard::{BitCard, BitCards, GRAND_MASK, HEARTS_MASK, KARO_MASK};
-use crate::solver::Variant::{Clubs, Diamonds, Grand, Hearts, Spades};
+use crate::solver::bitstates::BitGlobal;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::solver::Variant::{Clubs, Diamonds, Grand, Hearts, Null, NullHand, NullOuvert, NullOuvertHand, Ramsch, Spades};
 
 pub mod concurrent;
 pub mod synchronus;
@@ -14,6 +18,11 @@ pub enum Variant {
     Spades,
     Hearts,
     Diamonds,
+    Null,
+    NullHand,
+    NullOuvert,
+    NullOuvertHand,
+    Ramsch,
 }
 
 impl From<u8> for Variant {
@@ -24,15 +33,20 @@ impl From<u8> for Variant {
             2 => Spades,
             3 => Hearts,
             4 => Diamonds,
+            5 => Null,
+            6 => NullHand,
+            7 => NullOuvert,
+            8 => NullOuvertHand,
+            9 => Ramsch,
             _ => panic!()
         }
     }
 }
 
 impl Variant {
-    pub(crate) fn get_binary_mask(&self) -> u32 {
+    pub fn get_binary_mask(&self) -> u32 {
         match self {
-            Grand => {
+            Grand | Ramsch => {
                 GRAND_MASK
             }
             Diamonds => {
@@ -47,8 +61,17 @@ impl Variant {
             Clubs => {
                 bitboard::KREUZ_MASK | GRAND_MASK
             }
+            Null | NullHand | NullOuvert | NullOuvertHand => {
+                0
+            }
         }
     }
+
+    /// Null games have no trumps, use the order 7-8-9-10-J-Q-K-A and the declarer
+    /// has to lose every trick.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Null | NullHand | NullOuvert | NullOuvertHand)
+    }
 }
 
 
@@ -72,13 +95,21 @@ impl From<u8> for Player {
 
 
 impl Player {
-    pub(crate) fn get_next_player(&self) -> Player {
+    pub fn get_next_player(&self) -> Player {
         match self {
             Player::One => Player::Two,
             Player::Two => Player::Three,
             Player::Three => Player::One,
         }
     }
+
+    pub fn get_previous_player(&self) -> Player {
+        match self {
+            Player::One => Player::Three,
+            Player::Two => Player::One,
+            Player::Three => Player::Two,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -88,16 +119,25 @@ pub struct GlobalState {
     pub(crate) alone_player: Player,
     pub(crate) variant: Variant,
     pub(crate) skat_points: u8,
+    //every trick of the alone player is worth one instead of its card points
+    pub(crate) count_tricks: bool,
 }
 
 impl GlobalState {
     pub fn new(player_cards: (BitCards, BitCards, BitCards), skat: BitCards, alone_player: Player, variant: Variant) -> GlobalState {
+        //card points do not matter in null games
+        let skat_points = if variant.is_null() {
+            0
+        } else {
+            GlobalState::get_skat_points(skat)
+        };
         GlobalState {
             player_cards,
             skat,
             alone_player,
             variant,
-            skat_points: GlobalState::get_skat_points(skat),
+            skat_points,
+            count_tricks: false,
         }
     }
     fn get_skat_points(skat: BitCards) -> u8 {
@@ -109,7 +149,71 @@ impl GlobalState {
     }
 }
 
-pub(crate) fn calculate_current_suit_mask(first_card: BitCard, variant: &Variant) -> BitCards {
+///
+/// Cards of a game together with the player to move first and the declarer, player one by default.
+/// The skat can be empty if it is not known, the solvers fill it with the missing cards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deal {
+    pub player_cards: (BitCards, BitCards, BitCards),
+    pub skat: BitCards,
+    pub declarer: Player,
+    pub current_player: Player,
+    pub variant: Variant,
+}
+
+impl Deal {
+    pub fn new(player_cards: (BitCards, BitCards, BitCards), skat: BitCards, current_player: Player, variant: Variant) -> Deal {
+        Deal {
+            player_cards,
+            skat,
+            declarer: Player::One,
+            current_player,
+            variant,
+        }
+    }
+
+    pub fn with_declarer(mut self, declarer: Player) -> Deal {
+        self.declarer = declarer;
+        self
+    }
+
+    ///Cards still in the hands of the players
+    pub fn get_all_cards(&self) -> BitCards {
+        self.player_cards.0 | self.player_cards.1 | self.player_cards.2
+    }
+
+    pub(crate) fn get_global_state(&self) -> GlobalState {
+        GlobalState::new(self.player_cards, self.skat, self.declarer, self.variant)
+    }
+
+    ///The bit global state counts the points of a given skat even if not all other cards are dealt
+    pub(crate) fn get_bit_global(&self) -> BitGlobal {
+        let (p1, p2, p3) = self.player_cards;
+        let mut global_state = BitGlobal::new(p1, p2, p3, self.declarer, self.variant);
+        if self.skat.0 != 0 && !self.variant.is_null() {
+            global_state.skat_points = self.skat.get_cards_points();
+        }
+        global_state
+    }
+}
+
+///
+/// Common interface of the double dummy solvers, a solver is created for the cards of one deal
+/// and keeps its table between the calls.
+pub trait Solver {
+    fn from_deal(deal: &Deal) -> Self where Self: Sized;
+    ///Card points of the declarer including the skat, in null games the tricks of the declarer
+    fn solve_deal(&mut self, deal: &Deal) -> u8;
+    ///The card with the best score for the player to move
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard>;
+    ///The cards played until the end of the game if both sides play optimal
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard>;
+}
+
+pub fn calculate_current_suit_mask(first_card: BitCard, variant: &Variant) -> BitCards {
+    if variant.is_null() {
+        return BitCards(first_card.get_null_color_mask());
+    }
     if first_card.0 & variant.get_binary_mask() != 0 {
         return BitCards(variant.get_binary_mask());
     }
@@ -127,7 +231,7 @@ pub(crate) fn calculate_winner(winning_card: BitCard, global_state: &GlobalState
 }
 
 
-pub(crate) fn calculate_next_moves(current_cards: BitCards, suit_mask: Option<BitCards>) -> BitCards {
+pub fn calculate_next_moves(current_cards: BitCards, suit_mask: Option<BitCards>) -> BitCards {
     match suit_mask {
         None => {
             current_cards
@@ -143,13 +247,208 @@ pub(crate) fn calculate_next_moves(current_cards: BitCards, suit_mask: Option<Bi
 }
 
 
+///Reasons why cards of an open trick do not fit to a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrickError {
+    ///at most two cards can be on the table
+    TooManyCards,
+    NotASingleCard(BitCard),
+    ///the card is still in one of the hands
+    CardNotPlayed(BitCard),
+    ///the card does not belong to the player who had to play it
+    WrongPlayer(BitCard),
+    ///the card does not follow the suit although the player could
+    SuitNotFollowed(BitCard),
+    ///the players who played to the trick need one card less than the others
+    WrongCardCount,
+}
+
+impl Display for TrickError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TrickError::TooManyCards => write!(f, "more than two cards in the trick"),
+            TrickError::NotASingleCard(card) => write!(f, "{:#x} is not a single card", card.0),
+            TrickError::CardNotPlayed(card) => write!(f, "{} is still in a hand", card.get_human_representation()),
+            TrickError::WrongPlayer(card) => write!(f, "{} was not played by the right player", card.get_human_representation()),
+            TrickError::SuitNotFollowed(card) => write!(f, "{} does not follow suit", card.get_human_representation()),
+            TrickError::WrongCardCount => write!(f, "the hands do not have fitting amounts of cards"),
+        }
+    }
+}
+
+impl Error for TrickError {}
+
+///
+/// Checks the cards of an open trick, played in order by the players before the current player.
+/// Returns the suit mask of the trick, none if no card was played.
+pub(crate) fn validate_trick(player_cards: (BitCards, BitCards, BitCards), remaining_cards: BitCards, current_player: Player, played_cards: &[BitCard], variant: &Variant) -> Result<Option<BitCards>, TrickError> {
+    if played_cards.len() > 2 {
+        return Err(TrickError::TooManyCards);
+    }
+    let get_hand = |player: Player| match player {
+        Player::One => player_cards.0,
+        Player::Two => player_cards.1,
+        Player::Three => player_cards.2,
+    };
+    //the player who played the first card
+    let mut player = current_player;
+    for _ in played_cards {
+        player = player.get_previous_player();
+    }
+    let mut suit = None;
+    for card in played_cards {
+        if card.0.count_ones() != 1 {
+            return Err(TrickError::NotASingleCard(*card));
+        }
+        if card.0 & remaining_cards.0 != 0 {
+            return Err(TrickError::CardNotPlayed(*card));
+        }
+        let hand = get_hand(player);
+        if card.0 & hand.0 == 0 {
+            return Err(TrickError::WrongPlayer(*card));
+        }
+        match suit {
+            None => suit = Some(calculate_current_suit_mask(*card, variant)),
+            Some(mask) => {
+                let could_follow = (hand & remaining_cards & mask).0 != 0;
+                if card.0 & mask.0 == 0 && could_follow {
+                    return Err(TrickError::SuitNotFollowed(*card));
+                }
+            }
+        }
+        player = player.get_next_player();
+    }
+    let amount = (get_hand(current_player) & remaining_cards).0.count_ones();
+    for i in 1..3 {
+        player = player.get_previous_player();
+        let has_played = i <= played_cards.len();
+        if (get_hand(player) & remaining_cards).0.count_ones() + has_played as u32 != amount {
+            return Err(TrickError::WrongCardCount);
+        }
+    }
+    Ok(suit)
+}
+
+
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+    use crate::dataset::read_dataset;
+    use crate::solver::bitboard::{BitCard, BitCards};
+    use crate::solver::{Deal, Solver, TrickError};
     use crate::solver::bitboard::{HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_QUEEN, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_EIGHT, KARO_JACK, KARO_KING, KARO_NINE, KARO_QUEEN, KARO_SEVEN, KARO_TEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_NINE, KREUZ_QUEEN, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_EIGHT, PIQUS_JACK, PIQUS_KING, PIQUS_NINE, PIQUS_QUEEN, PIQUS_SEVEN, PIQUS_TEN};
     use crate::solver::{GlobalState, Player, Variant};
+    use crate::solver::concurrent::lazy_smp::LazyMoreEnhancedSolver;
+    use crate::solver::synchronus::ab::AbSolver;
+    use crate::solver::synchronus::ab_tt::DefaultSolver;
+    use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
     use crate::solver::synchronus::ab_tt_optimized::EnhancedSolver;
+    use crate::solver::synchronus::limits::{BoundedResult, SearchLimits};
     use crate::solver::synchronus::local_state::LState;
+    use crate::solver::synchronus::stats::SearchStats;
+
+    ///Solves every line of a data file with a new solver
+    pub(crate) fn run_data_file<S: Solver>(path: &str) {
+        run_data_file_with(path, S::from_deal);
+    }
+
+    ///Solves every line of a data file with the solver created for its deal, e.g. one with a small table
+    pub(crate) fn run_data_file_with<S: Solver>(path: &str, create: impl Fn(&Deal) -> S) {
+        for position in read_dataset(path).unwrap() {
+            let deal = position.get_deal().unwrap();
+            let mut solver = create(&deal);
+            assert_eq!(solver.solve_deal(&deal), position.result, "{position:?}");
+        }
+    }
+
+    ///
+    /// Solves the first positions of a data file and checks the counters, which only count with the stats feature.
+    /// Only the searches with memory enhanced test driver count iterations.
+    pub(crate) fn check_stats_file(path: &str, positions: usize, mtd_search: bool, solve: impl Fn(&Deal) -> (u8, SearchStats)) {
+        for position in read_dataset(path).unwrap().into_iter().take(positions) {
+            let (result, stats) = solve(&position.get_deal().unwrap());
+            assert_eq!(result, position.result, "{position:?}");
+            if cfg!(feature = "stats") {
+                assert!(stats.nodes > 0);
+                assert!(stats.tt_probes >= stats.tt_hits.total());
+                assert!(stats.tt_hits.total() >= stats.tt_cutoffs.total());
+                assert!(stats.cutoffs >= stats.first_move_cutoffs);
+                assert!(stats.peak_table_size > 0);
+                assert_eq!(stats.mtd_iterations > 0, mtd_search);
+            } else {
+                assert_eq!(stats, SearchStats::default());
+            }
+        }
+    }
+
+    ///
+    /// Analyzes the first positions of a data file and the open trick after their first card with one solver,
+    /// the score of every card has to match a new solver. The states are given by the cards in the hands,
+    /// the player to move and the cards of the trick.
+    pub(crate) fn check_analyze_moves_file<S>(path: &str, positions: usize, create: impl Fn(&Deal) -> S, analyze_moves: impl Fn(&mut S, BitCards, Player, &[BitCard]) -> Vec<(BitCard, u8)>) {
+        for position in read_dataset(path).unwrap().into_iter().take(positions) {
+            let deal = position.get_deal().unwrap();
+            let mut solver = create(&deal);
+            let scores = analyze_moves(&mut solver, deal.get_all_cards(), deal.current_player, &[]);
+            assert_eq!(scores[0].1, position.result, "{position:?}");
+            let global_state = deal.get_global_state();
+            let local_state = LState::new(deal.get_all_cards(), deal.current_player);
+            let next_state = local_state.get_next_states(&global_state)[0].0;
+            let first = local_state.get_played_card(&next_state);
+            for (card, score) in analyze_moves(&mut solver, next_state.remaining_cards, next_state.current_player, &[first]) {
+                let (after_state, _, achieved_points) = next_state.get_next_states(&global_state).into_iter()
+                    .find(|(after_state, _, _)| next_state.get_played_card(after_state) == card).unwrap();
+                assert_eq!(score, achieved_points + EnhancedSolver::new(deal.get_global_state()).solve(after_state), "{position:?}");
+            }
+        }
+    }
+
+    ///
+    /// Stops the searches of the first positions of a data file with every kind of limit, the bounds have to
+    /// contain the result. The solver is kept, so its table has to stay valid after the stopped searches.
+    pub(crate) fn check_limits_file<S: Solver>(path: &str, positions: usize, create: impl Fn(&Deal) -> S, solve_limited: impl Fn(&mut S, &Deal, &SearchLimits) -> BoundedResult) {
+        for position in read_dataset(path).unwrap().into_iter().take(positions) {
+            let deal = position.get_deal().unwrap();
+            let score = position.result;
+            let mut solver = create(&deal);
+            let result = solve_limited(&mut solver, &deal, &SearchLimits::new().with_max_nodes(2000));
+            assert!(result.aborted);
+            assert!(result.lower_bound <= score && score <= result.upper_bound);
+            let cancel = Arc::new(AtomicBool::new(true));
+            let result = solve_limited(&mut solver, &deal, &SearchLimits::new().with_cancel(cancel));
+            assert!(result.aborted);
+            assert!(result.lower_bound <= score && score <= result.upper_bound);
+            let result = solve_limited(&mut solver, &deal, &SearchLimits::new().with_timeout(Duration::ZERO));
+            assert!(result.lower_bound <= score && score <= result.upper_bound);
+            let result = solve_limited(&mut solver, &deal, &SearchLimits::new().with_timeout(Duration::from_secs(3600)));
+            assert!(!result.aborted);
+            assert_eq!(result.get_exact(), Some(score));
+            assert_eq!(solver.solve_deal(&deal), score);
+        }
+    }
+
+    ///
+    /// Plays the first two cards of the principal variation of the first positions of a data file,
+    /// the states created for the open trick have to be solved like the played states.
+    pub(crate) fn check_mid_trick_file<S>(path: &str, positions: usize, create: impl Fn(&Deal) -> S, solve: impl Fn(&mut S, BitCards, Player, &[BitCard]) -> u8) {
+        for position in read_dataset(path).unwrap().into_iter().take(positions) {
+            let deal = position.get_deal().unwrap();
+            let global_state = deal.get_global_state();
+            let mut reference = EnhancedSolver::new(deal.get_global_state());
+            let mut current_state = LState::new(deal.get_all_cards(), deal.current_player);
+            let variation = reference.principal_variation(current_state);
+            let mut solver = create(&deal);
+            for played in 1..3 {
+                current_state = current_state.get_next_states(&global_state).into_iter()
+                    .find(|(next_state, _, _)| current_state.get_played_card(next_state) == variation[played - 1]).unwrap().0;
+                let score = solve(&mut solver, current_state.remaining_cards, current_state.current_player, &variation[..played]);
+                assert_eq!(score, reference.solve(current_state), "{position:?}");
+            }
+        }
+    }
 
     #[test]
     fn long_solver_one() {
@@ -206,5 +505,85 @@ mod tests {
         let result = solver.solve(local_state);
         assert_eq!(result, 63)
     }
-}
 
+    #[test]
+    fn invalid_tricks() {
+        let player_one = KREUZ_ASS | KREUZ_TEN | HEARTS_ASS;
+        let player_two = KREUZ_KING | PIQUS_ASS | HEARTS_TEN;
+        let player_three = KREUZ_QUEEN | PIQUS_TEN | HEARTS_KING;
+        let global_state = GlobalState::new((player_one, player_two, player_three), BitCards(0), Player::One, Variant::Grand);
+        let all = player_one | player_two | player_three;
+        let without = |cards: u32| BitCards(all.0 & !cards);
+        let trick = LState::with_trick(without((KREUZ_ASS | KREUZ_KING).0), Player::Three, &[KREUZ_ASS, KREUZ_KING], &global_state);
+        assert!(trick.is_ok());
+        let trick = LState::with_trick(without((KREUZ_ASS | KREUZ_KING | KREUZ_QUEEN).0), Player::One, &[KREUZ_ASS, KREUZ_KING, KREUZ_QUEEN], &global_state);
+        assert_eq!(trick, Err(TrickError::TooManyCards));
+        let trick = LState::with_trick(without((KREUZ_ASS | KREUZ_TEN).0), Player::Two, &[BitCard((KREUZ_ASS | KREUZ_TEN).0)], &global_state);
+        assert_eq!(trick, Err(TrickError::NotASingleCard(BitCard((KREUZ_ASS | KREUZ_TEN).0))));
+        let trick = LState::with_trick(all, Player::Two, &[KREUZ_ASS], &global_state);
+        assert_eq!(trick, Err(TrickError::CardNotPlayed(KREUZ_ASS)));
+        let trick = LState::with_trick(without(KREUZ_KING.0), Player::One, &[KREUZ_KING], &global_state);
+        assert_eq!(trick, Err(TrickError::WrongPlayer(KREUZ_KING)));
+        let trick = LState::with_trick(without((KREUZ_ASS | PIQUS_ASS).0), Player::Three, &[KREUZ_ASS, PIQUS_ASS], &global_state);
+        assert_eq!(trick, Err(TrickError::SuitNotFollowed(PIQUS_ASS)));
+        let trick = LState::with_trick(without((KREUZ_ASS | KREUZ_TEN).0), Player::Two, &[KREUZ_ASS], &global_state);
+        assert_eq!(trick, Err(TrickError::WrongCardCount));
+    }
+
+    ///Solves the deal, playing the principal variation has to reach the solved score
+    fn check_variation<S: Solver>(deal: &Deal) -> u8 {
+        let mut solver = S::from_deal(deal);
+        let score = solver.solve_deal(deal);
+        let variation = solver.principal_variation_deal(deal);
+        assert_eq!(variation.len() as u32, deal.get_all_cards().0.count_ones());
+        assert_eq!(solver.best_move_deal(deal), variation.first().copied());
+        let global_state = deal.get_global_state();
+        let mut current_state = LState::new(deal.get_all_cards(), deal.current_player);
+        let mut points = global_state.skat_points;
+        for card in variation {
+            let (next_state, _, achieved_points) = current_state.get_next_states(&global_state).into_iter()
+                .find(|(next_state, _, _)| current_state.get_played_card(next_state) == card).unwrap();
+            current_state = next_state;
+            points += achieved_points;
+        }
+        assert_eq!(points, score, "{deal:?}");
+        score
+    }
+
+    ///Checks the principal variations of the first positions of a data file
+    pub(crate) fn check_variation_file<S: Solver>(path: &str, positions: usize) {
+        for position in read_dataset(path).unwrap().into_iter().take(positions) {
+            assert_eq!(check_variation::<S>(&position.get_deal().unwrap()), position.result, "{position:?}");
+        }
+    }
+
+    #[test]
+    fn solvers_agree() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(100) {
+            let deal = position.get_deal().unwrap();
+            let score = position.result;
+            assert_eq!(check_variation::<DefaultSolver>(&deal), score);
+            assert_eq!(check_variation::<EnhancedSolver>(&deal), score);
+            assert_eq!(check_variation::<MoreEnhancedSolver>(&deal), score);
+            assert_eq!(check_variation::<LazyMoreEnhancedSolver>(&deal), score);
+        }
+    }
+
+    #[test]
+    fn solvers_agree_declarer() {
+        for (number, position) in read_dataset("data/four_cards.txt").unwrap().into_iter().take(60).enumerate() {
+            let declarer = [Player::Two, Player::Three][number % 2];
+            let mut deal = position.get_deal().unwrap().with_declarer(declarer);
+            //every second deal has two of the missing cards in the skat
+            if number % 4 < 2 {
+                let missing = BitCards(!deal.get_all_cards().0);
+                deal.skat = missing.take(2).fold(BitCards(0), |cards, card| cards | card);
+            }
+            let score = check_variation::<AbSolver>(&deal);
+            assert_eq!(check_variation::<DefaultSolver>(&deal), score);
+            assert_eq!(check_variation::<EnhancedSolver>(&deal), score);
+            assert_eq!(check_variation::<MoreEnhancedSolver>(&deal), score);
+            assert_eq!(check_variation::<LazyMoreEnhancedSolver>(&deal), score);
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::solver::GlobalState;
+use crate::solver::bitboard::BitCard;
+use crate::solver::{Deal, GlobalState, Solver};
 use crate::solver::synchronus::local_state::LState;
 
 pub fn ab(local_state: LState, global_state: &GlobalState, alpha: i8, beta: i8) -> (i8, Option<LState>) {
@@ -8,7 +9,7 @@ pub fn ab(local_state: LState, global_state: &GlobalState, alpha: i8, beta: i8)
     let mut optimal_move: Option<LState> = None;
     let mut new_alpha = alpha;
     let mut new_beta = beta;
-    for (next_state, _ , achieved_points) in local_state.get_next_states(&global_state) {
+    for (next_state, _ , achieved_points) in local_state.get_next_states(global_state) {
         let achieved = achieved_points as i8;
         let poss_alpha_or_beta = achieved + ab(next_state,
                                                global_state,
@@ -34,14 +35,76 @@ pub fn ab(local_state: LState, global_state: &GlobalState, alpha: i8, beta: i8)
     }
 }
 
+///
+/// Alpha beta search without a table behind the solver interface. It is slow,
+/// but simple enough to check the other solvers on small deals.
+pub struct AbSolver {
+    pub global_state: GlobalState,
+}
+
+impl AbSolver {
+    pub fn new(global_state: GlobalState) -> AbSolver {
+        AbSolver {
+            global_state,
+        }
+    }
+
+    ///Card points of the declarer without the skat, in null games the tricks of the declarer
+    fn get_value(&self, local_state: LState) -> i8 {
+        ab(local_state, &self.global_state, 0, 120).0
+    }
+
+    ///The first successor with the best value for the player to move, every successor is searched with a full window
+    fn get_best_child(&self, local_state: LState) -> Option<LState> {
+        let is_max = local_state.is_max_node(&self.global_state);
+        let mut best: Option<(LState, i8)> = None;
+        for (next_state, _, achieved_points) in local_state.get_next_states(&self.global_state) {
+            let value = achieved_points as i8 + self.get_value(next_state);
+            let better = match best {
+                None => true,
+                Some((_, best_value)) => if is_max { value > best_value } else { value < best_value },
+            };
+            if better {
+                best = Some((next_state, value));
+            }
+        }
+        best.map(|(next_state, _)| next_state)
+    }
+}
+
+impl Solver for AbSolver {
+    fn from_deal(deal: &Deal) -> Self {
+        AbSolver::new(deal.get_global_state())
+    }
+
+    fn solve_deal(&mut self, deal: &Deal) -> u8 {
+        (self.get_value(LState::new(deal.get_all_cards(), deal.current_player)) + self.global_state.skat_points as i8) as u8
+    }
+
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard> {
+        let local_state = LState::new(deal.get_all_cards(), deal.current_player);
+        self.get_best_child(local_state).map(|next_state| local_state.get_played_card(&next_state))
+    }
+
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard> {
+        let mut current_state = LState::new(deal.get_all_cards(), deal.current_player);
+        let mut variation = Vec::new();
+        while let Some(next_state) = self.get_best_child(current_state) {
+            variation.push(current_state.get_played_card(&next_state));
+            current_state = next_state;
+        }
+        variation
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs;
     use crate::solver::bitboard::{BitCards, HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_QUEEN, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_EIGHT, KARO_JACK, KARO_KING, KARO_NINE, KARO_QUEEN, KARO_SEVEN, KARO_TEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_NINE, KREUZ_QUEEN, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_EIGHT, PIQUS_JACK, PIQUS_KING, PIQUS_NINE, PIQUS_QUEEN, PIQUS_SEVEN, PIQUS_TEN};
-    use crate::solver::{GlobalState, Player, Variant};
-    use crate::solver::synchronus::ab::{ab};
+    use crate::solver::{GlobalState, Player};
+    use crate::solver::synchronus::ab::{ab, AbSolver};
+    use crate::solver::tests::run_data_file;
     use crate::solver::synchronus::local_state::LState;
-    use crate::solver::Variant::Clubs;
+    use crate::solver::Variant::{Clubs, Null, NullOuvertHand};
 
     #[test]
     fn ab_normal_paper_one() {
@@ -79,78 +142,83 @@ mod tests {
         assert_eq!(result.0, 78)
     }
 
-    fn run_test(line: &str) -> (u8, u8) {
-        let data: Vec<&str> = line.split(',').collect();
-        let p1 = BitCards(data[0].parse::<u32>().unwrap());
-        let p2 = BitCards(data[1].parse::<u32>().unwrap());
-        let p3 = BitCards(data[2].parse::<u32>().unwrap());
-        let skat= BitCards(data[3].parse::<u32>().unwrap());
-        let current_player: Player = Player::from(data[4].parse::<u8>().unwrap());
-        let variant: Variant = Variant::from(data[5].parse::<u8>().unwrap());
-        let score = data[6].parse::<u8>().unwrap();
-        let local_state = LState::new(p1 | p2 | p3, current_player);
-        let global_state = GlobalState::new((p1, p2, p3), skat, Player::One, variant);
-        let result = ab(local_state, &global_state, 0, 120);
-        (result.0 as u8, score)
-    }
-
-
     #[test]
     fn ab_normal_four_cards() {
-        let input = fs::read_to_string("data/four_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/four_cards.txt");
     }
     #[test]
     #[ignore]
     fn ab_normal_seven_cards() {
-        let input = fs::read_to_string("data/seven_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/seven_cards.txt");
     }
     #[test]
     fn ab_normal_five_cards() {
-        let input = fs::read_to_string("data/five_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/five_cards.txt");
     }
     #[test]
     fn ab_normal_six_cards() {
-        let input = fs::read_to_string("data/six_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/six_cards.txt");
     }
     #[test]
     fn ab_normal_one_cards() {
-        let input = fs::read_to_string("data/one_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/one_cards.txt");
     }
     #[test]
     fn ab_normal_two_cards() {
-        let input = fs::read_to_string("data/two_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/two_cards.txt");
     }
     #[test]
     fn ab_normal_three_cards() {
-        let input = fs::read_to_string("data/three_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<AbSolver>("data/three_cards.txt");
+    }
+
+    #[test]
+    fn ab_null_hand_worked() {
+        let solve = |player_cards: (BitCards, BitCards, BitCards), declarer| {
+            let all_cards = player_cards.0 | player_cards.1 | player_cards.2;
+            let results: Vec<i8> = [Null, NullOuvertHand].iter()
+                .map(|variant| ab(LState::new(all_cards, Player::One), &GlobalState::new(player_cards, BitCards(0), declarer, *variant), 0, 120).0)
+                .collect();
+            assert_eq!(results[0], results[1]);
+            results[0]
+        };
+        //the sevens of the declarer lose every trick
+        assert_eq!(solve((KREUZ_SEVEN | HEARTS_SEVEN, KREUZ_EIGHT | HEARTS_EIGHT, KREUZ_NINE | HEARTS_NINE), Player::One), 0);
+        //the Kreuz Ass takes a trick whenever Kreuz is played, the declarer can not get rid of it
+        assert_eq!(solve((KREUZ_ASS | HEARTS_SEVEN, KREUZ_SEVEN | HEARTS_EIGHT, KREUZ_EIGHT | PIQUS_SEVEN), Player::One), 1);
+        //the ten is below the jack
+        assert_eq!(solve((BitCards(HEARTS_TEN.0), BitCards(HEARTS_JACK.0), BitCards(HEARTS_NINE.0)), Player::One), 0);
+        assert_eq!(solve((BitCards(HEARTS_JACK.0), BitCards(HEARTS_TEN.0), BitCards(HEARTS_NINE.0)), Player::One), 1);
+        //jacks are no trumps, the Kreuz Jack is the highest Kreuz card and the Karo Jack does not win a Hearts trick
+        assert_eq!(solve((BitCards(KREUZ_JACK.0), BitCards(KREUZ_SEVEN.0), BitCards(PIQUS_ASS.0)), Player::One), 1);
+        assert_eq!(solve((BitCards(HEARTS_SEVEN.0), BitCards(KARO_JACK.0), BitCards(HEARTS_EIGHT.0)), Player::One), 0);
+        //the defenders try to give a trick to the declarer in the second seat, who can always play under them
+        assert_eq!(solve((PIQUS_SEVEN | PIQUS_ASS, PIQUS_EIGHT | KARO_SEVEN, PIQUS_NINE | KARO_EIGHT), Player::Two), 0);
+        assert_eq!(solve((BitCards(KARO_SEVEN.0), BitCards(KARO_EIGHT.0), BitCards(KARO_ASS.0)), Player::Three), 1);
     }
-}
 
+    #[test]
+    fn ab_null_one_cards() {
+        run_data_file::<AbSolver>("data/null_one_cards.txt");
+    }
+    #[test]
+    fn ab_null_two_cards() {
+        run_data_file::<AbSolver>("data/null_two_cards.txt");
+    }
+    #[test]
+    fn ab_null_three_cards() {
+        run_data_file::<AbSolver>("data/null_three_cards.txt");
+    }
+    #[test]
+    fn ab_null_four_cards() {
+        run_data_file::<AbSolver>("data/null_four_cards.txt");
+    }
+    #[test]
+    fn ab_null_five_cards() {
+        run_data_file::<AbSolver>("data/null_five_cards.txt");
+    }
+    #[test]
+    fn ab_null_six_cards() {
+        run_data_file::<AbSolver>("data/null_six_cards.txt");
+    }
+}
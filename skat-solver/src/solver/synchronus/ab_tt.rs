@@ -1,35 +1,99 @@
 use std::cmp::{max, min};
 use std::collections::HashMap;
-use crate::solver::{GlobalState, Player};
+use crate::solver::bitboard::BitCard;
+use crate::solver::{Deal, GlobalState, Player, Solver};
 use crate::solver::synchronus::ab_tt::Bounds::{LowerBound, UpperBound, Valid};
 use crate::solver::synchronus::local_state::LState;
+use crate::solver::synchronus::stats::SearchStats;
+use crate::solver::synchronus::trans_table::TranspositionTable;
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Bounds {
     Valid,
     LowerBound,
     UpperBound
 }
 
-pub struct DefaultSolver {
+pub struct DefaultSolver<T: TranspositionTable<(u32, Player)> = HashMap<(u32, Player), (i8, Bounds)>> {
     pub global_state: GlobalState,
-    pub look_up_table: HashMap<(u32, Player), (i8, Bounds)>
+    pub look_up_table: T,
+    stats: SearchStats,
 }
 
-
 impl DefaultSolver {
+    pub fn new(global_state: GlobalState) -> DefaultSolver {
+        Self::with_table(global_state, Default::default())
+    }
+}
+
+impl<T: TranspositionTable<(u32, Player)>> DefaultSolver<T> {
+    pub fn with_table(global_state: GlobalState, look_up_table: T) -> DefaultSolver<T> {
+        Self {
+            global_state,
+            look_up_table,
+            stats: Default::default(),
+        }
+    }
+
     pub fn solve(&mut self, local_state: LState) -> i8 {
         self.ab_tt(local_state, 0, 120) + self.global_state.skat_points as i8
     }
 
+    ///Solves the state and returns the statistics of the search, which are only collected with the `stats` feature
+    pub fn solve_with_stats(&mut self, local_state: LState) -> (i8, SearchStats) {
+        self.stats = Default::default();
+        let result = self.solve(local_state);
+        self.stats.update_table_size(|| self.look_up_table.len());
+        (result, self.stats)
+    }
+
+    ///The card with the best score for the player to move
+    pub fn best_move(&mut self, local_state: LState) -> Option<BitCard> {
+        let value = self.ab_tt(local_state, 0, 120);
+        self.get_best_child(local_state, value).map(|(next_state, _)| local_state.get_played_card(&next_state))
+    }
+
+    ///The cards played until the end of the game if both sides play optimal
+    pub fn principal_variation(&mut self, local_state: LState) -> Vec<BitCard> {
+        let mut value = self.ab_tt(local_state, 0, 120);
+        let mut current_state = local_state;
+        let mut variation = Vec::new();
+        while let Some((next_state, next_value)) = self.get_best_child(current_state, value) {
+            variation.push(current_state.get_played_card(&next_state));
+            current_state = next_state;
+            value = next_value;
+        }
+        variation
+    }
+
+    ///Finds the first successor keeping the exact value of the state
+    fn get_best_child(&mut self, local_state: LState, value: i8) -> Option<(LState, i8)> {
+        let is_max = local_state.is_max_node(&self.global_state);
+        for (next_state, _, achieved_points) in local_state.get_next_states(&self.global_state) {
+            let next_value = value - achieved_points as i8;
+            if is_max {
+                if self.ab_tt(next_state, next_value - 1, next_value) >= next_value {
+                    return Some((next_state, next_value));
+                }
+            } else if self.ab_tt(next_state, next_value, next_value + 1) <= next_value {
+                return Some((next_state, next_value));
+            }
+        }
+        None
+    }
+
 
+    fn insert(&mut self, local_state: &LState, score: i8, bound: Bounds) {
+        self.look_up_table.put(local_state.get_hash(), score, bound, local_state.get_depth());
+    }
     fn try_insert(&mut self, local_state: &LState, score: i8, bound: Bounds) {
         if local_state.is_full_node() {
-            self.look_up_table.insert(local_state.get_hash(), (score, bound));
+            self.insert(local_state, score, bound);
         }
     }
     fn ab_tt(&mut self, local_state: LState, agoof: i8, bgoof: i8) -> i8 {
+        self.stats.add_node();
         if local_state.is_terminal() {
             return 0;
         }
@@ -37,9 +101,12 @@ impl DefaultSolver {
         let mut new_beta = bgoof;
 
         if local_state.is_full_node() {
-            if let Some(result) = self.look_up_table.get(&local_state.get_hash()) {
+            let entry = self.look_up_table.get(local_state.get_hash());
+            self.stats.add_probe(entry.map(|entry| entry.1));
+            if let Some(result) = entry {
                 match result.1 {
                     Valid => {
+                        self.stats.add_tt_cutoff(Valid);
                         return result.0;
                     }
                     LowerBound => {
@@ -50,12 +117,13 @@ impl DefaultSolver {
                     }
                 }
                 if new_alpha >= new_beta {
+                    self.stats.add_tt_cutoff(result.1);
                     return result.0;
                 }
             }
         }
         let mut changed = false;
-        for (next_state, _ , achieved_points) in local_state.get_next_states(&self.global_state) {
+        for (index, (next_state, _ , achieved_points)) in local_state.get_next_states(&self.global_state).into_iter().enumerate() {
             let t_q = achieved_points as i8;
             let succ_val = t_q + self.ab_tt(next_state, new_alpha - t_q, new_beta - t_q);
             if local_state.is_max_node(&self.global_state) {
@@ -65,6 +133,7 @@ impl DefaultSolver {
                 }
                 //result = max(result, succ_val);
                 if new_alpha >= new_beta {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_alpha, LowerBound);
                     return new_alpha;
                 }
@@ -75,6 +144,7 @@ impl DefaultSolver {
                     new_beta = succ_val;
                 }
                 if new_beta <= new_alpha {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_beta, UpperBound);
                     return new_beta;
                 }
@@ -90,22 +160,41 @@ impl DefaultSolver {
             return result;
         }
         if changed {
-            self.look_up_table.insert(local_state.get_hash(), (result, Valid));
+            self.insert(&local_state, result, Valid);
             return result;
         }
         if local_state.is_max_node(&self.global_state) {
-            self.look_up_table.insert(local_state.get_hash(), (result, UpperBound));
+            self.insert(&local_state, result, UpperBound);
         } else {
-            self.look_up_table.insert(local_state.get_hash(), (result, LowerBound));
+            self.insert(&local_state, result, LowerBound);
         }
         result
     }
 }
 
 
+impl<T: TranspositionTable<(u32, Player)> + Default> Solver for DefaultSolver<T> {
+    fn from_deal(deal: &Deal) -> Self {
+        Self::with_table(deal.get_global_state(), T::default())
+    }
+
+    fn solve_deal(&mut self, deal: &Deal) -> u8 {
+        self.solve(LState::new(deal.get_all_cards(), deal.current_player)) as u8
+    }
+
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard> {
+        self.best_move(LState::new(deal.get_all_cards(), deal.current_player))
+    }
+
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard> {
+        self.principal_variation(LState::new(deal.get_all_cards(), deal.current_player))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use crate::solver::tests::{check_stats_file, check_variation_file, run_data_file, run_data_file_with};
+    use crate::solver::synchronus::trans_table::FixedTable;
     use crate::solver::bitboard::{BitCards, HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_QUEEN, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_EIGHT, KARO_JACK, KARO_KING, KARO_NINE, KARO_QUEEN, KARO_SEVEN, KARO_TEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_NINE, KREUZ_QUEEN, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_EIGHT, PIQUS_JACK, PIQUS_KING, PIQUS_NINE, PIQUS_QUEEN, PIQUS_SEVEN, PIQUS_TEN};
     use crate::solver::{GlobalState, Player, Variant};
     use crate::solver::synchronus::ab_tt::DefaultSolver;
@@ -128,10 +217,7 @@ mod tests {
         );
         let local_state = LState::new(all_cards, Player::One);
 
-        let mut solver = DefaultSolver {
-            global_state,
-            look_up_table: Default::default(),
-        };
+        let mut solver = DefaultSolver::new(global_state);
         let result = solver.ab_tt(local_state, 0, 120);
         assert_eq!(result, 7);
     }
@@ -162,127 +248,69 @@ mod tests {
             Variant::Clubs,
         );
         let local_state = LState::new(all_cards, Player::One);
-        let mut solver = DefaultSolver {
-            global_state,
-            look_up_table: Default::default(),
-        };
+        let mut solver = DefaultSolver::new(global_state);
         let result = solver.ab_tt(local_state, 0, 120);
         assert_eq!(result, 78)
     }
 
 
-    fn run_test(line: &str) -> Result<(), (u8, u8)> {
-        let data: Vec<&str> = line.split(',').collect();
-        let p1 = BitCards(data[0].parse::<u32>().unwrap());
-        let p2 = BitCards(data[1].parse::<u32>().unwrap());
-        let p3 = BitCards(data[2].parse::<u32>().unwrap());
-        let skat = BitCards(data[3].parse::<u32>().unwrap());
-        let current_player: Player = Player::from(data[4].parse::<u8>().unwrap());
-        let variant: Variant = Variant::from(data[5].parse::<u8>().unwrap());
-        let score = data[6].parse::<u8>().unwrap();
-        let local_state = LState::new(p1 | p2 | p3, current_player);
-        let global_state = GlobalState::new((p1, p2, p3), skat, Player::One, variant);
-        let mut solver = DefaultSolver {
-            global_state,
-            look_up_table: Default::default(),
-        };
-        let result = solver.ab_tt(local_state, 0, 120);
-        assert!((0..=120).contains(&result));
-        let result = result as u8;
-        if result == score {
-            return Ok(());
-        }
-        Err((result, score))
-    }
 
     #[test]
     fn ab_tt_normal_four_cards() {
-        let input = fs::read_to_string("data/four_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<DefaultSolver>("data/four_cards.txt");
     }
 
     #[test]
     fn ab_tt_normal_seven_cards() {
-        let input = fs::read_to_string("data/seven_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<DefaultSolver>("data/seven_cards.txt");
     }
 
     #[test]
     fn ab_tt_normal_five_cards() {
-        let input = fs::read_to_string("data/five_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<DefaultSolver>("data/five_cards.txt");
     }
 
     #[test]
     fn ab_tt_normal_six_cards() {
-        let input = fs::read_to_string("data/six_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<DefaultSolver>("data/six_cards.txt");
     }
 
     #[test]
     fn ab_tt_normal_one_cards() {
-        let input = fs::read_to_string("data/one_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<DefaultSolver>("data/one_cards.txt");
     }
 
     #[test]
     fn ab_tt_normal_two_cards() {
-        let input = fs::read_to_string("data/two_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<DefaultSolver>("data/two_cards.txt");
     }
 
     #[test]
     fn ab_tt_normal_three_cards() {
-        let input = fs::read_to_string("data/three_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
+        run_data_file::<DefaultSolver>("data/three_cards.txt");
+    }
 
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes += 1 }
-        }
-        assert_eq!(successes, len);
+    #[test]
+    fn ab_tt_null_five_cards() {
+        run_data_file::<DefaultSolver>("data/null_five_cards.txt");
+    }
+
+    #[test]
+    fn ab_tt_principal_variation() {
+        check_variation_file::<DefaultSolver>("data/four_cards.txt", usize::MAX);
+    }
+
+    #[test]
+    fn ab_tt_fixed_table() {
+        //a small table has to replace entries, the results stay exact
+        run_data_file_with("data/four_cards.txt", |deal| DefaultSolver::with_table(deal.get_global_state(), FixedTable::with_memory(1 << 10)));
+    }
+
+    #[test]
+    fn ab_tt_stats() {
+        check_stats_file("data/six_cards.txt", 50, false, |deal| {
+            let (result, stats) = DefaultSolver::new(deal.get_global_state()).solve_with_stats(LState::new(deal.get_all_cards(), deal.current_player));
+            (result as u8, stats)
+        });
     }
 }
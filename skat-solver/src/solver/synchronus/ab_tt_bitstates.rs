@@ -1,33 +1,161 @@
-use std::cmp::{max, min};
+use std::cmp::{max, min, Reverse};
 use fxhash::FxHashMap;
+use crate::solver::bitboard::BitCard;
 use crate::solver::bitstates::{BitGlobal, BitLocal};
+use crate::solver::{Deal, Solver};
 use crate::solver::synchronus::ab_tt::Bounds;
 use crate::solver::synchronus::ab_tt::Bounds::{LowerBound, UpperBound, Valid};
+use crate::solver::synchronus::limits::{BoundedResult, Budget, SearchLimits};
+use crate::solver::synchronus::stats::SearchStats;
+use crate::solver::synchronus::threshold::{Target, ThresholdResult};
+use crate::solver::synchronus::trans_table::TranspositionTable;
 
 
-pub struct MoreEnhancedSolver {
+pub struct MoreEnhancedSolver<T: TranspositionTable<u32> = FxHashMap<u32, (i8, Bounds)>> {
     pub global_state: BitGlobal,
-    pub look_up_table: FxHashMap<u32, (i8, Bounds)>
+    pub look_up_table: T,
+    budget: Budget,
+    stats: SearchStats,
 }
 impl MoreEnhancedSolver {
     pub fn new(global_state: BitGlobal) -> MoreEnhancedSolver {
+        Self::with_table(global_state, Default::default())
+    }
+}
+impl<T: TranspositionTable<u32>> MoreEnhancedSolver<T> {
+    pub fn with_table(global_state: BitGlobal, look_up_table: T) -> MoreEnhancedSolver<T> {
         Self {
             global_state,
-            look_up_table: Default::default()
+            look_up_table,
+            budget: Default::default(),
+            stats: Default::default(),
         }
     }
 
     #[inline]
     fn try_insert(&mut self, local_state: &BitLocal, score: i8, bound: Bounds) {
         if local_state.is_full_node() {
-            self.look_up_table.insert(local_state.get_hash(), (score, bound));
+            self.insert(local_state, score, bound);
         }
     }
     #[inline(always)]
-    fn insert(&mut self, pos: u32, score: i8, bound: Bounds) {
-        self.look_up_table.insert(pos, (score, bound));
+    fn insert(&mut self, local_state: &BitLocal, score: i8, bound: Bounds) {
+        self.look_up_table.put(local_state.get_hash(), score, bound, local_state.get_depth(&self.global_state));
+    }
+    ///Stored results are only valid for one kind of score
+    fn set_count_tricks(&mut self, count_tricks: bool) {
+        if self.global_state.count_tricks != count_tricks {
+            self.global_state.set_count_tricks(count_tricks);
+            self.look_up_table.clear();
+        }
     }
+
+    ///Null window search whether player one reaches the target
+    pub fn solve_target(&mut self, local_state: BitLocal, target: Target) -> ThresholdResult {
+        self.set_count_tricks(target.counts_tricks());
+        let goal = target.get_goal(self.global_state.skat_points);
+        let reached = self.ab_tt(local_state, goal - 1, goal) >= goal;
+        let mut best_move = None;
+        if reached == local_state.is_max_node(&self.global_state) {
+            for (next_state, achieved_points) in local_state.get_next_states(&self.global_state) {
+                let t_q = achieved_points as i8;
+                let succ_val = t_q + self.ab_tt(next_state, goal - 1 - t_q, goal - t_q);
+                if (succ_val >= goal) == reached {
+                    best_move = Some(local_state.get_played_card(&next_state, &self.global_state));
+                    break;
+                }
+            }
+        }
+        ThresholdResult {
+            reached,
+            best_move,
+        }
+    }
+
     pub fn solve(&mut self, local_state: BitLocal) -> u8 {
+        self.set_count_tricks(false);
+        self.get_value(local_state) as u8 + self.global_state.skat_points
+    }
+
+    ///Solves the state and returns the statistics of the search, which are only collected with the `stats` feature
+    pub fn solve_with_stats(&mut self, local_state: BitLocal) -> (u8, SearchStats) {
+        self.stats = Default::default();
+        let result = self.solve(local_state);
+        (result, self.stats)
+    }
+
+    ///The card with the best score for the player to move
+    pub fn best_move(&mut self, local_state: BitLocal) -> Option<BitCard> {
+        self.set_count_tricks(false);
+        let value = self.get_value(local_state);
+        self.get_best_child(local_state, value).map(|(next_state, _)| local_state.get_played_card(&next_state, &self.global_state))
+    }
+
+    ///The cards played until the end of the game if both sides play optimal
+    pub fn principal_variation(&mut self, local_state: BitLocal) -> Vec<BitCard> {
+        self.set_count_tricks(false);
+        let mut value = self.get_value(local_state);
+        let mut current_state = local_state;
+        let mut variation = Vec::new();
+        while let Some((next_state, next_value)) = self.get_best_child(current_state, value) {
+            variation.push(current_state.get_played_card(&next_state, &self.global_state));
+            current_state = next_state;
+            value = next_value;
+        }
+        variation
+    }
+
+    ///Score of player one (skat included) after every legal card of the player to move,
+    /// the best card for the player to move comes first
+    pub fn analyze_moves(&mut self, local_state: BitLocal) -> Vec<(BitCard, u8)> {
+        self.set_count_tricks(false);
+        let mut scores = Vec::new();
+        for (next_state, achieved_points) in local_state.get_next_states(&self.global_state) {
+            //the table is kept, so siblings profit from each other
+            let score = achieved_points + self.get_value(next_state) as u8 + self.global_state.skat_points;
+            scores.push((local_state.get_played_card(&next_state, &self.global_state), score));
+        }
+        if local_state.is_max_node(&self.global_state) {
+            scores.sort_by_key(|(_, score)| Reverse(*score));
+        } else {
+            scores.sort_by_key(|(_, score)| *score);
+        }
+        scores
+    }
+
+    ///Finds the first successor keeping the exact value of the state
+    fn get_best_child(&mut self, local_state: BitLocal, value: i8) -> Option<(BitLocal, i8)> {
+        let is_max = local_state.is_max_node(&self.global_state);
+        for (next_state, achieved_points) in local_state.get_next_states(&self.global_state) {
+            let next_value = value - achieved_points as i8;
+            if is_max {
+                if self.ab_tt(next_state, next_value - 1, next_value) >= next_value {
+                    return Some((next_state, next_value));
+                }
+            } else if self.ab_tt(next_state, next_value, next_value + 1) <= next_value {
+                return Some((next_state, next_value));
+            }
+        }
+        None
+    }
+
+    ///Solves until one of the limits is hit, the bounds of the null window loop are returned
+    pub fn solve_limited(&mut self, local_state: BitLocal, limits: &SearchLimits) -> BoundedResult {
+        self.set_count_tricks(false);
+        self.budget = Budget::new(limits);
+        let (lower_bound, upper_bound) = self.get_bounds(local_state);
+        let aborted = self.budget.is_aborted();
+        self.budget = Default::default();
+        BoundedResult::new(lower_bound, upper_bound, self.global_state.skat_points, aborted)
+    }
+
+    ///Exact score of the state without the skat
+    fn get_value(&mut self, local_state: BitLocal) -> i8 {
+        self.get_bounds(local_state).0
+    }
+
+    ///Proven lower and upper bound of the score, equal unless the budget ran out
+    fn get_bounds(&mut self, local_state: BitLocal) -> (i8, i8) {
         //did not improve performance maybe for larger n >7
         let mut min: i8 = 0;
         let mut max = 120;
@@ -39,26 +167,39 @@ impl MoreEnhancedSolver {
                 med = max / 2;
             }
             let r = self.ab_tt(local_state, med, med + 1);   // use a null depth window to know if the actual score is greater or smaller than med
+            self.stats.add_mtd_iteration();
+            self.stats.update_table_size(|| self.look_up_table.len());
+            if self.budget.is_aborted() {
+                break;
+            }
             if r <= med {
                 max = r;
             } else {
                 min = r;
             }
         }
-        min as u8 + self.global_state.skat_points
+        (min, max)
     }
 
     pub fn ab_tt(&mut self, local_state: BitLocal, agoof: i8, bgoof: i8) -> i8 {
-        if local_state.is_terminal(self.global_state.skat) {
+        self.stats.add_node();
+        //the result of a stopped search is never used
+        if self.budget.visit() {
+            return 0;
+        }
+        if local_state.is_terminal(&self.global_state) {
             return 0;
         }
         let mut new_alpha = agoof;
         let mut new_beta = bgoof;
 
         if local_state.is_full_node() {
-            if let Some(result) = self.look_up_table.get(&local_state.get_hash()) {
+            let entry = self.look_up_table.get(local_state.get_hash());
+            self.stats.add_probe(entry.map(|entry| entry.1));
+            if let Some(result) = entry {
                 match result.1 {
                     Valid => {
+                        self.stats.add_tt_cutoff(Valid);
                         return result.0
                     }
                     LowerBound => {
@@ -69,24 +210,30 @@ impl MoreEnhancedSolver {
                     }
                 }
                 if new_alpha >= new_beta {
+                    self.stats.add_tt_cutoff(result.1);
                     return result.0
                 }
             }
         }
 
         let is_max = local_state.is_max_node(&self.global_state);
-        for (next_state , achieved_points) in local_state.get_next_states(&self.global_state) {
+        for (index, (next_state, achieved_points)) in local_state.get_next_states(&self.global_state).into_iter().enumerate() {
             let t_q = achieved_points as i8;
             let succ_val= t_q + self.ab_tt(next_state, new_alpha - t_q, new_beta - t_q);
+            if self.budget.is_aborted() {
+                return 0;
+            }
             if is_max {
                 new_alpha = max(new_alpha, succ_val);
                 if new_alpha >= new_beta {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_alpha, LowerBound);
                     return new_alpha
                 }
             } else {
                 new_beta = min(new_beta, succ_val);
                 if new_beta <= new_alpha {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_beta, UpperBound);
                     return new_beta
                 }
@@ -103,58 +250,166 @@ impl MoreEnhancedSolver {
         }
         if is_max {
             if new_alpha != agoof {
-                self.insert(local_state.get_hash(), result, Valid);
+                self.insert(&local_state, result, Valid);
             }
             else {
-                self.insert(local_state.get_hash(), result, UpperBound);
+                self.insert(&local_state, result, UpperBound);
             }
         } else if result != bgoof {
-            self.insert(local_state.get_hash(), result, Valid);
+            self.insert(&local_state, result, Valid);
         }
         else {
-            self.insert(local_state.get_hash(), result, LowerBound);
+            self.insert(&local_state, result, LowerBound);
         }
         result
     }
 }
+impl<T: TranspositionTable<u32> + Default> Solver for MoreEnhancedSolver<T> {
+    fn from_deal(deal: &Deal) -> Self {
+        Self::with_table(deal.get_bit_global(), T::default())
+    }
+
+    fn solve_deal(&mut self, deal: &Deal) -> u8 {
+        self.solve(BitLocal::new(deal.get_all_cards().0, deal.current_player, &self.global_state))
+    }
+
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard> {
+        self.best_move(BitLocal::new(deal.get_all_cards().0, deal.current_player, &self.global_state))
+    }
+
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard> {
+        self.principal_variation(BitLocal::new(deal.get_all_cards().0, deal.current_player, &self.global_state))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs;
-    use crate::solver::bitboard::{BitCards};
+    use crate::solver::tests::{check_analyze_moves_file, check_limits_file, check_mid_trick_file, check_stats_file, check_variation_file, run_data_file, run_data_file_with};
+    use crate::solver::synchronus::trans_table::FixedTable;
+    use crate::dataset::read_dataset;
+    use crate::solver::bitboard::{HEARTS_EIGHT, HEARTS_SEVEN, KARO_EIGHT, KARO_NINE, KARO_SEVEN, KREUZ_JACK};
     use crate::solver::{Player, Variant};
     use crate::solver::bitstates::{BitGlobal, BitLocal};
     use crate::solver::synchronus::ab_tt_bitstates::MoreEnhancedSolver;
+    use crate::solver::synchronus::threshold::Target;
 
 
-    fn run_test(line: &str) -> Result<(), (u8, u8)> {
-        let data: Vec<&str> = line.split(',').collect();
-        let p1 = BitCards(data[0].parse::<u32>().unwrap());
-        let p2 = BitCards(data[1].parse::<u32>().unwrap());
-        let p3 = BitCards(data[2].parse::<u32>().unwrap());
-        let skat= BitCards(data[3].parse::<u32>().unwrap());
-        let current_player: Player = Player::from(data[4].parse::<u8>().unwrap());
-        let variant: Variant = Variant::from(data[5].parse::<u8>().unwrap());
-        let score = data[6].parse::<u8>().unwrap();
-        let global_state = BitGlobal::new(p1, p2, p3, variant);
-        let local_state = BitLocal::new((p1 | p2 | p3).0, current_player, global_state.skat);
-        let mut solver = MoreEnhancedSolver::new(global_state);
-        let result = solver.solve(local_state);
-        assert!((0..=120).contains(&result));
-        if result == score {
-            return Ok(());
+    #[test]
+    fn ab_tt_opt1_full() {
+        run_data_file::<MoreEnhancedSolver>("data/full_game.txt");
+    }
+
+    #[test]
+    fn ab_tt_opt1_null_six() {
+        run_data_file::<MoreEnhancedSolver>("data/null_six_cards.txt");
+    }
+
+    #[test]
+    fn ab_tt_opt1_null_full() {
+        run_data_file::<MoreEnhancedSolver>("data/null_full_game.txt");
+    }
+
+    #[test]
+    fn ab_tt_opt1_target() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(300) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let score = position.result;
+            let global_state = BitGlobal::new(p1, p2, p3, Player::One, variant);
+            let local_state = BitLocal::new((p1 | p2 | p3).0, current_player, &global_state);
+            let mut solver = MoreEnhancedSolver::new(global_state);
+            assert!(solver.solve_target(local_state, Target::Points(score)).reached);
+            assert!(!solver.solve_target(local_state, Target::Points(score + 1)).reached);
+            //every trick is worth at most 21 points in five cards without jacks
+            let tricks = solver.solve_target(local_state, Target::Tricks(5));
+            if tricks.reached {
+                assert_eq!(solver.solve(local_state), (p1 | p2 | p3).get_cards_points());
+            }
         }
-        Err((result, score))
     }
+
     #[test]
-    fn ab_tt_opt1_full() {
-        let input = fs::read_to_string("data/full_game.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
+    fn ab_tt_opt1_target_tricks() {
+        let player_one = KREUZ_JACK | KARO_SEVEN;
+        let player_two = KARO_EIGHT | HEARTS_SEVEN;
+        let player_three = KARO_NINE | HEARTS_EIGHT;
+        let global_state = BitGlobal::new(player_one, player_two, player_three, Player::One, Variant::Grand);
+        let local_state = BitLocal::new((player_one | player_two | player_three).0, Player::One, &global_state);
+        let mut solver = MoreEnhancedSolver::new(global_state);
+        assert!(solver.solve_target(local_state, Target::Points(2)).reached);
+        let result = solver.solve_target(local_state, Target::Tricks(1));
+        assert!(result.reached);
+        assert!(result.best_move.is_some());
+        assert!(!solver.solve_target(local_state, Target::Tricks(2)).reached);
+        assert_eq!(solver.solve(local_state), 2);
+    }
 
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
+    #[test]
+    fn ab_tt_opt1_principal_variation() {
+        check_variation_file::<MoreEnhancedSolver>("data/five_cards.txt", 200);
+    }
+
+    #[test]
+    fn ab_tt_opt1_declarer_seats() {
+        for position in read_dataset("data/six_cards.txt").unwrap().into_iter().take(100) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let score = position.result;
+            let mut solver = MoreEnhancedSolver::new(BitGlobal::new(p1, p2, p3, Player::One, variant));
+            let best_move = solver.best_move(BitLocal::new((p1 | p2 | p3).0, current_player, &solver.global_state));
+            //the same deal with the declarer sitting in the second and third seat
+            let seats = [(Player::Two, (p3, p1, p2)), (Player::Three, (p2, p3, p1))];
+            for (declarer, (s1, s2, s3)) in seats {
+                let current_player = Player::from((current_player as u8 + declarer as u8) % 3);
+                let global_state = BitGlobal::new(s1, s2, s3, declarer, variant);
+                let local_state = BitLocal::new((p1 | p2 | p3).0, current_player, &global_state);
+                assert_eq!(global_state.get_declarer(), declarer);
+                assert_eq!(local_state.get_current_player(&global_state), current_player);
+                let mut solver = MoreEnhancedSolver::new(global_state);
+                assert_eq!(solver.best_move(local_state), best_move);
+                assert_eq!(solver.solve(local_state), score);
+            }
         }
-        assert_eq!(successes, len);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ab_tt_opt1_mid_trick() {
+        check_mid_trick_file("data/six_cards.txt", 100, |deal| MoreEnhancedSolver::new(deal.get_bit_global()), |solver, remaining, player, trick| {
+            let local_state = BitLocal::with_trick(remaining.0, player, trick, &solver.global_state).unwrap();
+            solver.solve(local_state)
+        });
+    }
+
+    #[test]
+    fn ab_tt_opt1_analyze_moves() {
+        check_analyze_moves_file("data/four_cards.txt", 200, |deal| MoreEnhancedSolver::new(deal.get_bit_global()), |solver, remaining, player, trick| {
+            let local_state = BitLocal::with_trick(remaining.0, player, trick, &solver.global_state).unwrap();
+            solver.analyze_moves(local_state)
+        });
+    }
+
+    #[test]
+    fn ab_tt_opt1_fixed_table() {
+        //a small table has to replace entries, the results stay exact
+        run_data_file_with("data/six_cards.txt", |deal| MoreEnhancedSolver::with_table(deal.get_bit_global(), FixedTable::with_memory(1 << 12)));
+    }
+
+    #[test]
+    fn ab_tt_opt1_limits() {
+        check_limits_file("data/full_game.txt", 3, |deal| MoreEnhancedSolver::new(deal.get_bit_global()), |solver, deal, limits| {
+            let local_state = BitLocal::new(deal.get_all_cards().0, deal.current_player, &solver.global_state);
+            solver.solve_limited(local_state, limits)
+        });
+    }
+
+    #[test]
+    fn ab_tt_opt1_stats() {
+        check_stats_file("data/six_cards.txt", 50, true, |deal| {
+            let global_state = deal.get_bit_global();
+            let local_state = BitLocal::new(deal.get_all_cards().0, deal.current_player, &global_state);
+            MoreEnhancedSolver::new(global_state).solve_with_stats(local_state)
+        });
+    }
+}
@@ -1,20 +1,32 @@
-use std::cmp::{max, min};
+use std::cmp::{max, min, Reverse};
 use std::collections::HashMap;
-use crate::solver::bitboard::BitCards;
-use crate::solver::GlobalState;
+use crate::solver::bitboard::{BitCard, BitCards};
+use crate::solver::{Deal, GlobalState, Solver};
 use crate::solver::synchronus::ab_tt::Bounds;
 use crate::solver::synchronus::ab_tt::Bounds::{LowerBound, UpperBound, Valid};
 use crate::solver::synchronus::local_state::LState;
+use crate::solver::synchronus::limits::{BoundedResult, Budget, SearchLimits};
+use crate::solver::synchronus::stats::SearchStats;
+use crate::solver::synchronus::threshold::{Target, ThresholdResult};
+use crate::solver::synchronus::trans_table::TranspositionTable;
 
-pub struct EnhancedSolver {
+pub struct EnhancedSolver<T: TranspositionTable<u32> = HashMap<u32, (i8, Bounds)>> {
     pub global_state: GlobalState,
-    pub look_up_table: HashMap<u32, (i8, Bounds)>
+    pub look_up_table: T,
+    budget: Budget,
+    stats: SearchStats,
 }
 
 
 impl EnhancedSolver {
+    pub fn new(global_state: GlobalState) -> EnhancedSolver {
+        Self::with_table(global_state, Default::default())
+    }
+}
 
-    pub fn new(mut global_state: GlobalState) -> EnhancedSolver {
+impl<T: TranspositionTable<u32>> EnhancedSolver<T> {
+
+    pub fn with_table(mut global_state: GlobalState, look_up_table: T) -> EnhancedSolver<T> {
         if global_state.skat == BitCards(0) {
             let all_cards = BitCards(!(global_state.player_cards.0 | global_state.player_cards.1 | global_state.player_cards.2).0);
             let first_card = all_cards.get_next_card_in_binary();
@@ -24,22 +36,137 @@ impl EnhancedSolver {
         }
         Self {
             global_state,
-            look_up_table: Default::default(),
+            look_up_table,
+            budget: Default::default(),
+            stats: Default::default(),
         }
     }
 
     fn try_insert(&mut self, local_state: &LState, score: i8, bound: Bounds) {
         if local_state.is_full_node() {
-            self.look_up_table.insert(local_state.get_hash_better(&self.global_state), (score, bound));
+            self.insert(local_state, score, bound);
         }
     }
-    fn insert(&mut self, pos: u32, score: i8, bound: Bounds) {
-        self.look_up_table.insert(pos, (score, bound));
+    fn insert(&mut self, local_state: &LState, score: i8, bound: Bounds) {
+        self.look_up_table.put(local_state.get_hash_better(&self.global_state), score, bound, local_state.get_depth());
+    }
+    ///Stored results are only valid for one kind of score
+    fn set_count_tricks(&mut self, count_tricks: bool) {
+        if self.global_state.count_tricks != count_tricks {
+            self.global_state.count_tricks = count_tricks;
+            self.look_up_table.clear();
+        }
+    }
+
+    ///Null window search whether the declarer reaches the target
+    pub fn solve_target(&mut self, local_state: LState, target: Target) -> ThresholdResult {
+        self.set_count_tricks(target.counts_tricks());
+        let goal = target.get_goal(self.global_state.skat_points);
+        let reached = self.ab_tt(local_state, goal - 1, goal) >= goal;
+        let mut best_move = None;
+        if reached == local_state.is_max_node(&self.global_state) {
+            for (next_state, _, achieved_points) in local_state.get_next_states(&self.global_state) {
+                let t_q = achieved_points as i8;
+                let succ_val = t_q + self.ab_tt(next_state, goal - 1 - t_q, goal - t_q);
+                if (succ_val >= goal) == reached {
+                    best_move = Some(local_state.get_played_card(&next_state));
+                    break;
+                }
+            }
+        }
+        ThresholdResult {
+            reached,
+            best_move,
+        }
     }
+
     pub fn solve(&mut self, local_state: LState) -> u8 {
+        self.set_count_tricks(false);
+        self.get_value(local_state) as u8 + self.global_state.skat_points
+    }
+
+    ///Solves the state and returns the statistics of the search, which are only collected with the `stats` feature
+    pub fn solve_with_stats(&mut self, local_state: LState) -> (u8, SearchStats) {
+        self.stats = Default::default();
+        let result = self.solve(local_state);
+        (result, self.stats)
+    }
+
+    ///The card with the best score for the player to move
+    pub fn best_move(&mut self, local_state: LState) -> Option<BitCard> {
+        self.set_count_tricks(false);
+        let value = self.get_value(local_state);
+        self.get_best_child(local_state, value).map(|(next_state, _)| local_state.get_played_card(&next_state))
+    }
+
+    ///The cards played until the end of the game if both sides play optimal
+    pub fn principal_variation(&mut self, local_state: LState) -> Vec<BitCard> {
+        self.set_count_tricks(false);
+        let mut value = self.get_value(local_state);
+        let mut current_state = local_state;
+        let mut variation = Vec::new();
+        while let Some((next_state, next_value)) = self.get_best_child(current_state, value) {
+            variation.push(current_state.get_played_card(&next_state));
+            current_state = next_state;
+            value = next_value;
+        }
+        variation
+    }
+
+    ///Score of player one (skat included) after every legal card of the player to move,
+    /// the best card for the player to move comes first
+    pub fn analyze_moves(&mut self, local_state: LState) -> Vec<(BitCard, u8)> {
+        self.set_count_tricks(false);
+        let mut scores = Vec::new();
+        for (next_state, _, achieved_points) in local_state.get_next_states(&self.global_state) {
+            //the table is kept, so siblings profit from each other
+            let score = achieved_points + self.get_value(next_state) as u8 + self.global_state.skat_points;
+            scores.push((local_state.get_played_card(&next_state), score));
+        }
+        if local_state.is_max_node(&self.global_state) {
+            scores.sort_by_key(|(_, score)| Reverse(*score));
+        } else {
+            scores.sort_by_key(|(_, score)| *score);
+        }
+        scores
+    }
+
+    ///Finds the first successor keeping the exact value of the state
+    fn get_best_child(&mut self, local_state: LState, value: i8) -> Option<(LState, i8)> {
+        let is_max = local_state.is_max_node(&self.global_state);
+        for (next_state, _, achieved_points) in local_state.get_next_states(&self.global_state) {
+            let next_value = value - achieved_points as i8;
+            if is_max {
+                if self.ab_tt(next_state, next_value - 1, next_value) >= next_value {
+                    return Some((next_state, next_value));
+                }
+            } else if self.ab_tt(next_state, next_value, next_value + 1) <= next_value {
+                return Some((next_state, next_value));
+            }
+        }
+        None
+    }
+
+    ///Solves until one of the limits is hit, the bounds of the null window loop are returned
+    pub fn solve_limited(&mut self, local_state: LState, limits: &SearchLimits) -> BoundedResult {
+        self.set_count_tricks(false);
+        self.budget = Budget::new(limits);
+        let (lower_bound, upper_bound) = self.get_bounds(local_state);
+        let aborted = self.budget.is_aborted();
+        self.budget = Default::default();
+        BoundedResult::new(lower_bound, upper_bound, self.global_state.skat_points, aborted)
+    }
+
+    ///Exact score of the state without the skat
+    fn get_value(&mut self, local_state: LState) -> i8 {
+        self.get_bounds(local_state).0
+    }
+
+    ///Proven lower and upper bound of the score, equal unless the budget ran out
+    fn get_bounds(&mut self, local_state: LState) -> (i8, i8) {
         //did not improve performance maybe for larger n >7
         let mut min: i8 = 0;
-        let mut max = local_state.remaining_cards.get_cards_points() as i8;
+        let mut max = local_state.get_max_score(&self.global_state);
         while min < max {
             let mut med = min + (max - min) / 2;
             if med <= 0 && min / 2 < med {
@@ -48,16 +175,26 @@ impl EnhancedSolver {
                 med = max / 2;
             }
             let r = self.ab_tt(local_state, med, med + 1);   // use a null depth window to know if the actual score is greater or smaller than med
+            self.stats.add_mtd_iteration();
+            self.stats.update_table_size(|| self.look_up_table.len());
+            if self.budget.is_aborted() {
+                break;
+            }
             if r <= med {
                 max = r;
             } else {
                 min = r;
             }
         }
-        min as u8 + self.global_state.skat_points
+        (min, max)
     }
 
     pub fn ab_tt(&mut self, local_state: LState, agoof: i8, bgoof: i8) -> i8 {
+        self.stats.add_node();
+        //the result of a stopped search is never used
+        if self.budget.visit() {
+            return 0;
+        }
         if local_state.is_terminal() {
             return 0;
         }
@@ -65,9 +202,12 @@ impl EnhancedSolver {
         let mut new_beta = bgoof;
 
         if local_state.is_full_node() {
-            if let Some(result) = self.look_up_table.get(&local_state.get_hash_better(&self.global_state)) {
+            let entry = self.look_up_table.get(local_state.get_hash_better(&self.global_state));
+            self.stats.add_probe(entry.map(|entry| entry.1));
+            if let Some(result) = entry {
                 match result.1 {
                     Valid => {
+                        self.stats.add_tt_cutoff(Valid);
                         return result.0
                     }
                     LowerBound => {
@@ -78,24 +218,30 @@ impl EnhancedSolver {
                     }
                 }
                 if new_alpha >= new_beta {
+                    self.stats.add_tt_cutoff(result.1);
                     return result.0
                 }
             }
         }
 
         let is_max = local_state.is_max_node(&self.global_state);
-        for (next_state, _ , achieved_points) in local_state.get_next_states(&self.global_state) {
+        for (index, (next_state, _ , achieved_points)) in local_state.get_next_states(&self.global_state).into_iter().enumerate() {
             let t_q = achieved_points as i8;
             let succ_val= t_q + self.ab_tt(next_state, new_alpha - t_q, new_beta - t_q);
+            if self.budget.is_aborted() {
+                return 0;
+            }
             if is_max {
                 new_alpha = max(new_alpha, succ_val);
                 if new_alpha >= new_beta {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_alpha, LowerBound);
                     return new_alpha
                 }
             } else {
                 new_beta = min(new_beta, succ_val);
                 if new_beta <= new_alpha {
+                    self.stats.add_cutoff(index);
                     self.try_insert(&local_state, new_beta, UpperBound);
                     return new_beta
                 }
@@ -112,27 +258,48 @@ impl EnhancedSolver {
         }
         if is_max {
             if new_alpha != agoof {
-                self.insert(local_state.get_hash_better(&self.global_state), result, Valid);
+                self.insert(&local_state, result, Valid);
             }
             else {
-                self.insert(local_state.get_hash_better(&self.global_state), result, UpperBound);
+                self.insert(&local_state, result, UpperBound);
             }
         } else if result != bgoof {
-            self.insert(local_state.get_hash_better(&self.global_state), result, Valid);
+            self.insert(&local_state, result, Valid);
         }
         else {
-            self.insert(local_state.get_hash_better(&self.global_state), result, LowerBound);
+            self.insert(&local_state, result, LowerBound);
         }
         result
     }
 }
+impl<T: TranspositionTable<u32> + Default> Solver for EnhancedSolver<T> {
+    fn from_deal(deal: &Deal) -> Self {
+        Self::with_table(deal.get_global_state(), T::default())
+    }
+
+    fn solve_deal(&mut self, deal: &Deal) -> u8 {
+        self.solve(LState::new(deal.get_all_cards(), deal.current_player))
+    }
+
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard> {
+        self.best_move(LState::new(deal.get_all_cards(), deal.current_player))
+    }
+
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard> {
+        self.principal_variation(LState::new(deal.get_all_cards(), deal.current_player))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use crate::solver::tests::{check_analyze_moves_file, check_limits_file, check_mid_trick_file, check_stats_file, check_variation_file, run_data_file, run_data_file_with};
+    use crate::solver::synchronus::trans_table::FixedTable;
+    use crate::dataset::read_dataset;
     use crate::solver::bitboard::{BitCards, HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_QUEEN, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_EIGHT, KARO_JACK, KARO_KING, KARO_NINE, KARO_QUEEN, KARO_SEVEN, KARO_TEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_NINE, KREUZ_QUEEN, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_EIGHT, PIQUS_JACK, PIQUS_KING, PIQUS_NINE, PIQUS_QUEEN, PIQUS_SEVEN, PIQUS_TEN};
     use crate::solver::{GlobalState, Player, Variant};
     use crate::solver::synchronus::ab_tt_optimized::EnhancedSolver;
     use crate::solver::synchronus::local_state::LState;
+    use crate::solver::synchronus::threshold::Target;
 
     #[test]
     fn ab_tt_opt_paper_one() {
@@ -188,124 +355,165 @@ mod tests {
 
 
 
-    fn run_test(line: &str) -> Result<(), (u8, u8)> {
-        let data: Vec<&str> = line.split(',').collect();
-        let p1 = BitCards(data[0].parse::<u32>().unwrap());
-        let p2 = BitCards(data[1].parse::<u32>().unwrap());
-        let p3 = BitCards(data[2].parse::<u32>().unwrap());
-        let skat= BitCards(data[3].parse::<u32>().unwrap());
-        let current_player: Player = Player::from(data[4].parse::<u8>().unwrap());
-        let variant: Variant = Variant::from(data[5].parse::<u8>().unwrap());
-        let score = data[6].parse::<u8>().unwrap();
-        let local_state = LState::new(p1 | p2 | p3, current_player);
-        let global_state = GlobalState::new((p1, p2, p3), skat, Player::One, variant);
-        let mut solver = EnhancedSolver::new(global_state);
-        let result = solver.solve(local_state);
-        assert!((0..=120).contains(&result));
-        if result == score {
-            return Ok(());
-        }
-        Err((result, score))
-    }
 
     #[test]
     fn ab_tt_opt_four_cards() {
-        let input = fs::read_to_string("data/four_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/four_cards.txt");
     }
     #[test]
     fn ab_tt_opt_seven_cards() {
-        let input = fs::read_to_string("data/seven_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/seven_cards.txt");
     }
     #[test]
     fn ab_tt_opt_five_cards() {
-        let input = fs::read_to_string("data/five_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/five_cards.txt");
     }
     #[test]
     fn ab_tt_opt_six_cards() {
-        let input = fs::read_to_string("data/six_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/six_cards.txt");
     }
 
     #[test]
     fn ab_tt_opt_one_cards() {
-        let input = fs::read_to_string("data/one_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/one_cards.txt");
     }
 
     #[test]
     fn ab_tt_opt_two_cards() {
-        let input = fs::read_to_string("data/two_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/two_cards.txt");
     }
 
     #[test]
     fn ab_tt_opt_three_cards() {
-        let input = fs::read_to_string("data/three_cards.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
-
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
-        }
-        assert_eq!(successes, len);
+        run_data_file::<EnhancedSolver>("data/three_cards.txt");
     }
 
     #[test]
     fn ab_tt_opt_full() {
-        let input = fs::read_to_string("data/full_game.txt").unwrap();
-        let len = input.lines().count();
-        let mut successes = 0;
+        run_data_file::<EnhancedSolver>("data/full_game.txt");
+    }
+
+    #[test]
+    fn ab_tt_opt_null_four_cards() {
+        run_data_file::<EnhancedSolver>("data/null_four_cards.txt");
+    }
+
+    #[test]
+    fn ab_tt_opt_null_six_cards() {
+        run_data_file::<EnhancedSolver>("data/null_six_cards.txt");
+    }
 
-        for line in input.lines() {
-            let result = run_test(line);
-            if let Ok(()) = result { successes +=1 }
+    #[test]
+    fn ab_tt_opt_null_full() {
+        run_data_file::<EnhancedSolver>("data/null_full_game.txt");
+    }
+
+    #[test]
+    fn ab_tt_opt_target_points() {
+        for position in read_dataset("data/five_cards.txt").unwrap().into_iter().take(300) {
+            let (p1, p2, p3) = position.player_cards;
+            let current_player = position.current_player;
+            let variant = position.variant;
+            let score = position.result;
+            let local_state = LState::new(p1 | p2 | p3, current_player);
+            let mut solver = EnhancedSolver::new(GlobalState::new((p1, p2, p3), BitCards(0), Player::One, variant));
+            let result = solver.solve_target(local_state, Target::Points(score));
+            assert!(result.reached);
+            assert_eq!(result.best_move.is_some(), current_player == Player::One);
+            let result = solver.solve_target(local_state, Target::Points(score + 1));
+            assert!(!result.reached);
+            assert_eq!(result.best_move.is_some(), current_player != Player::One);
+            //the move has to keep the result
+            let best_move = result.best_move.unwrap_or_else(|| local_state.get_next_states(&solver.global_state)[0].0.current_played_cards.0);
+            let next_state = local_state.get_next_states(&solver.global_state).into_iter()
+                .find(|(next_state, _, _)| local_state.get_played_card(next_state) == best_move).unwrap().0;
+            assert!(!solver.solve_target(next_state, Target::Points(score + 1)).reached);
         }
-        assert_eq!(successes, len);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn ab_tt_opt_target_tricks() {
+        //player one gets every point, but the defenders take the trick of karo
+        let player_one = KREUZ_JACK | KARO_SEVEN;
+        let player_two = KARO_EIGHT | HEARTS_SEVEN;
+        let player_three = KARO_NINE | HEARTS_EIGHT;
+        let global_state = GlobalState::new((player_one, player_two, player_three), BitCards(0), Player::One, Variant::Grand);
+        let local_state = LState::new(player_one | player_two | player_three, Player::One);
+        let mut solver = EnhancedSolver::new(global_state);
+        assert!(solver.solve_target(local_state, Target::Points(2)).reached);
+        assert!(solver.solve_target(local_state, Target::Tricks(1)).reached);
+        let result = solver.solve_target(local_state, Target::Tricks(2));
+        assert!(!result.reached);
+        assert_eq!(result.best_move, None);
+        assert_eq!(solver.solve(local_state), 2);
+
+        let player_one = KREUZ_JACK | HEARTS_ASS;
+        let player_two = PIQUS_JACK | KARO_SEVEN;
+        let player_three = HEARTS_EIGHT | KARO_NINE;
+        let global_state = GlobalState::new((player_one, player_two, player_three), BitCards(0), Player::One, Variant::Grand);
+        let local_state = LState::new(player_one | player_two | player_three, Player::One);
+        let mut solver = EnhancedSolver::new(global_state);
+        //the jack has to be played first, otherwise player two trumps the ace
+        let result = solver.solve_target(local_state, Target::Tricks(2));
+        assert!(result.reached);
+        assert_eq!(result.best_move, Some(KREUZ_JACK));
+    }
+
+    #[test]
+    fn ab_tt_opt_target_null() {
+        //the declarer can not get rid of the karo ace
+        let player_one = KARO_ASS | HEARTS_SEVEN;
+        let player_two = KARO_SEVEN | HEARTS_EIGHT;
+        let player_three = KARO_EIGHT | HEARTS_NINE;
+        let global_state = GlobalState::new((player_one, player_two, player_three), BitCards(0), Player::One, Variant::Null);
+        let local_state = LState::new(player_one | player_two | player_three, Player::Two);
+        let mut solver = EnhancedSolver::new(global_state);
+        let result = solver.solve_target(local_state, Target::Tricks(1));
+        assert!(result.reached);
+        assert!(result.best_move.is_some());
+        assert!(!solver.solve_target(local_state, Target::Tricks(2)).reached);
+        assert_eq!(solver.solve(local_state), 1);
+    }
+
+    #[test]
+    fn ab_tt_opt_mid_trick() {
+        check_mid_trick_file("data/six_cards.txt", 100, |deal| EnhancedSolver::new(deal.get_global_state()), |solver, remaining, player, trick| {
+            let local_state = LState::with_trick(remaining, player, trick, &solver.global_state).unwrap();
+            solver.solve(local_state)
+        });
+    }
+
+    #[test]
+    fn ab_tt_opt_principal_variation() {
+        check_variation_file::<EnhancedSolver>("data/five_cards.txt", 200);
+    }
+
+    #[test]
+    fn ab_tt_opt_analyze_moves() {
+        check_analyze_moves_file("data/four_cards.txt", 200, |deal| EnhancedSolver::new(deal.get_global_state()), |solver, remaining, player, trick| {
+            let local_state = LState::with_trick(remaining, player, trick, &solver.global_state).unwrap();
+            solver.analyze_moves(local_state)
+        });
+    }
+
+    #[test]
+    fn ab_tt_opt_fixed_table() {
+        //a small table has to replace entries, the results stay exact
+        run_data_file_with("data/five_cards.txt", |deal| EnhancedSolver::with_table(deal.get_global_state(), FixedTable::with_memory(1 << 10)));
+    }
+
+    #[test]
+    fn ab_tt_opt_limits() {
+        check_limits_file("data/full_game.txt", 3, |deal| EnhancedSolver::new(deal.get_global_state()), |solver, deal, limits| {
+            let local_state = LState::new(deal.get_all_cards(), deal.current_player);
+            solver.solve_limited(local_state, limits)
+        });
+    }
+
+    #[test]
+    fn ab_tt_opt_stats() {
+        check_stats_file("data/six_cards.txt", 50, true, |deal| {
+            EnhancedSolver::new(deal.get_global_state()).solve_with_stats(LState::new(deal.get_all_cards(), deal.current_player))
+        });
+    }
+}
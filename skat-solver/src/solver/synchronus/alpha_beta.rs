@@ -1,7 +1,7 @@
 use std::cmp::{max, min};
 use arrayvec::ArrayVec;
 use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better, EMPTY_CARD};
-use crate::solver::{calculate_current_suit_mask, calculate_next_moves, calculate_winner, GlobalState, Player};
+use crate::solver::{calculate_current_suit_mask, calculate_next_moves, calculate_winner, Deal, GlobalState, Player, Solver};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct LocalState {
@@ -189,12 +189,43 @@ fn minimax(local_state: LocalState, global_state: &GlobalState, alpha: u8, beta:
 
 
 
+//minimax behind the solver interface, only used to check it against the data files
+struct MinimaxSolver {
+    global_state: GlobalState,
+}
+
+impl Solver for MinimaxSolver {
+    fn from_deal(deal: &Deal) -> Self {
+        MinimaxSolver {
+            global_state: deal.get_global_state(),
+        }
+    }
+
+    fn solve_deal(&mut self, deal: &Deal) -> u8 {
+        minimax(LocalState::new(deal.get_all_cards(), deal.current_player), &self.global_state, 0, 120).0
+    }
+
+    fn best_move_deal(&mut self, deal: &Deal) -> Option<BitCard> {
+        self.principal_variation_deal(deal).first().copied()
+    }
+
+    fn principal_variation_deal(&mut self, deal: &Deal) -> Vec<BitCard> {
+        let mut current_state = LocalState::new(deal.get_all_cards(), deal.current_player);
+        let mut variation = Vec::new();
+        while let (_, Some(next_state)) = minimax(current_state, &self.global_state, 0, 120) {
+            variation.push(BitCard(current_state.remaining_cards.0 & !next_state.remaining_cards.0));
+            current_state = next_state;
+        }
+        variation
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs;
     use crate::solver::bitboard::{BitCards, HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_QUEEN, HEARTS_SEVEN, HEARTS_TEN, KARO_ASS, KARO_EIGHT, KARO_JACK, KARO_KING, KARO_NINE, KARO_QUEEN, KARO_SEVEN, KARO_TEN, KREUZ_ASS, KREUZ_EIGHT, KREUZ_JACK, KREUZ_KING, KREUZ_NINE, KREUZ_QUEEN, KREUZ_SEVEN, KREUZ_TEN, PIQUS_ASS, PIQUS_EIGHT, PIQUS_JACK, PIQUS_KING, PIQUS_NINE, PIQUS_QUEEN, PIQUS_SEVEN, PIQUS_TEN};
-    use crate::solver::{GlobalState, Player, Variant};
-    use crate::solver::synchronus::alpha_beta::{LocalState, minimax};
+    use crate::solver::{GlobalState, Player};
+    use crate::solver::synchronus::alpha_beta::{LocalState, minimax, MinimaxSolver};
+    use crate::solver::tests::run_data_file;
     use crate::solver::Variant::Clubs;
 
 
@@ -245,81 +276,37 @@ mod tests {
         assert_eq!(result.0, 78)
     }
 
-    fn run_test(line: &str) -> (u8, u8) {
-        let data: Vec<&str> = line.split(',').collect();
-        let p1 = BitCards(data[0].parse::<u32>().unwrap());
-        let p2 = BitCards(data[1].parse::<u32>().unwrap());
-        let p3 = BitCards(data[2].parse::<u32>().unwrap());
-        let skat= BitCards(data[3].parse::<u32>().unwrap());
-        let current_player: Player = Player::from(data[4].parse::<u8>().unwrap());
-        let variant: Variant = Variant::from(data[5].parse::<u8>().unwrap());
-        let score = data[6].parse::<u8>().unwrap();
-        let local_state = LocalState::new(p1 | p2 | p3, current_player);
-        let global_state = GlobalState::new((p1, p2, p3), skat, Player::One, variant);
-        let result = minimax(local_state, &global_state, 0, 120);
-        (result.0, score)
-    }
-
-
     #[test]
     fn alpha_beta_four_cards() {
-        let input = fs::read_to_string("data/four_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/four_cards.txt");
     }
     #[test]
     #[ignore]
     fn alpha_beta_seven_cards() {
-        let input = fs::read_to_string("data/seven_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/seven_cards.txt");
     }
     #[test]
     fn alpha_beta_five_cards() {
-        let input = fs::read_to_string("data/five_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/five_cards.txt");
     }
     #[test]
     fn alpha_beta_six_cards() {
-        let input = fs::read_to_string("data/six_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/six_cards.txt");
     }
 
     #[test]
     fn alpha_beta_one_cards() {
-        let input = fs::read_to_string("data/one_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/one_cards.txt");
     }
 
     #[test]
     fn alpha_beta_two_cards() {
-        let input = fs::read_to_string("data/two_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/two_cards.txt");
     }
 
     #[test]
     fn alpha_beta_three_cards() {
-        let input = fs::read_to_string("data/three_cards.txt").unwrap();
-        for line in input.lines() {
-            let result = run_test(line);
-            assert_eq!(result.0, result.1)
-        }
+        run_data_file::<MinimaxSolver>("data/three_cards.txt");
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+//the clock and the token are only looked at every few nodes
+const CHECK_INTERVAL: u64 = 1 << 12;
+
+///
+/// Limits of a single solve, a search stops at the first limit it hits.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub max_nodes: Option<u64>,
+    pub deadline: Option<Instant>,
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+    pub fn new() -> SearchLimits {
+        Default::default()
+    }
+
+    pub fn with_max_nodes(mut self, max_nodes: u64) -> SearchLimits {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Instant) -> SearchLimits {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn with_timeout(self, timeout: Duration) -> SearchLimits {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    ///The search stops as soon as the token is set to true
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> SearchLimits {
+        self.cancel = Some(cancel);
+        self
+    }
+}
+
+///
+/// Result of a limited solve, the score is proven to be within the bounds.
+/// Both bounds include the skat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundedResult {
+    pub lower_bound: u8,
+    pub upper_bound: u8,
+    ///a limit was hit before the search finished
+    pub aborted: bool,
+}
+
+impl BoundedResult {
+    pub(crate) fn new(lower_bound: i8, upper_bound: i8, skat_points: u8, aborted: bool) -> BoundedResult {
+        BoundedResult {
+            lower_bound: lower_bound as u8 + skat_points,
+            upper_bound: upper_bound as u8 + skat_points,
+            aborted,
+        }
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.lower_bound == self.upper_bound
+    }
+
+    pub fn get_exact(&self) -> Option<u8> {
+        if self.is_exact() {
+            Some(self.lower_bound)
+        } else {
+            None
+        }
+    }
+}
+
+///Running state of the limits inside a solver, unlimited by default
+#[derive(Debug)]
+pub(crate) struct Budget {
+    nodes: u64,
+    max_nodes: u64,
+    deadline: Option<Instant>,
+    cancel: Option<Arc<AtomicBool>>,
+    aborted: bool,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget {
+            nodes: 0,
+            max_nodes: u64::MAX,
+            deadline: None,
+            cancel: None,
+            aborted: false,
+        }
+    }
+}
+
+impl Budget {
+    pub(crate) fn new(limits: &SearchLimits) -> Budget {
+        Budget {
+            nodes: 0,
+            max_nodes: limits.max_nodes.unwrap_or(u64::MAX),
+            deadline: limits.deadline,
+            cancel: limits.cancel.clone(),
+            aborted: false,
+        }
+    }
+
+    ///Counts a node, true if the search has to stop
+    #[inline(always)]
+    pub(crate) fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes & (CHECK_INTERVAL - 1) == 0 || self.nodes >= self.max_nodes {
+            self.check();
+        }
+        self.aborted
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    #[cold]
+    fn check(&mut self) {
+        if self.nodes >= self.max_nodes {
+            self.aborted = true;
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.aborted = true;
+        }
+        if self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            self.aborted = true;
+        }
+    }
+}
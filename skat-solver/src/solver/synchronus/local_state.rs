@@ -1,6 +1,6 @@
 use arrayvec::ArrayVec;
 use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better, EMPTY_CARD};
-use crate::solver::{calculate_current_suit_mask, calculate_next_moves, calculate_winner, GlobalState, Player};
+use crate::solver::{calculate_current_suit_mask, calculate_next_moves, calculate_winner, GlobalState, Player, TrickError, validate_trick};
 
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -24,6 +24,22 @@ impl LState {
         }
     }
 
+    ///
+    /// Position inside a trick, played_cards are the cards on the table in the order they were played.
+    /// remaining_cards are the cards still in the hands, so without the played ones.
+    pub fn with_trick(remaining_cards: BitCards, current_player: Player, played_cards: &[BitCard], global_state: &GlobalState) -> Result<LState, TrickError> {
+        let current_suit = validate_trick(global_state.player_cards, remaining_cards, current_player, played_cards, &global_state.variant)?;
+        Ok(LState {
+            remaining_cards,
+            current_played_cards: (
+                played_cards.first().copied().unwrap_or(EMPTY_CARD),
+                played_cards.get(1).copied().unwrap_or(EMPTY_CARD),
+            ),
+            current_player,
+            current_suit,
+        })
+    }
+
     pub(crate) fn get_hash(&self) -> (u32, Player) {
         (self.remaining_cards.0, self.current_player)
     }
@@ -43,10 +59,22 @@ impl LState {
         }
     }
 
+    ///Amount of cards left in the hands, used to keep deep entries in the table
+    #[inline(always)]
+    pub(crate) fn get_depth(&self) -> u8 {
+        self.remaining_cards.0.count_ones() as u8
+    }
+
     #[inline(always)]
     pub(crate) fn is_full_node(&self) -> bool {
         self.current_suit.is_none()
     }
+
+    ///The card played to get from this state to the next state
+    #[inline(always)]
+    pub(crate) fn get_played_card(&self, next_state: &LState) -> BitCard {
+        BitCard(self.remaining_cards.0 & !next_state.remaining_cards.0)
+    }
 }
 
 
@@ -58,9 +86,20 @@ impl LState {
         self.remaining_cards.0 == 0
     }
 
+    ///In null games the score counts the tricks of the declarer, so the defenders maximize it
     #[inline(always)]
     pub(crate) fn is_max_node(&self, global_state: &GlobalState) -> bool {
-        self.current_player == global_state.alone_player
+        (self.current_player == global_state.alone_player) != global_state.variant.is_null()
+    }
+
+    ///Upper bound for the score which can still be achieved from this state
+    pub(crate) fn get_max_score(&self, global_state: &GlobalState) -> i8 {
+        //cards already on the table belong to the current trick
+        let on_table = self.current_played_cards.0 | self.current_played_cards.1;
+        if global_state.variant.is_null() || global_state.count_tricks {
+            return (self.remaining_cards.0.count_ones() + on_table.0.count_ones()).div_ceil(3) as i8;
+        }
+        (self.remaining_cards.get_cards_points() + on_table.get_cards_points()) as i8
     }
 
     #[inline(always)]
@@ -104,10 +143,12 @@ impl LState {
                     let winner_card = calculate_who_won_better(self.current_played_cards.0, self.current_played_cards.1, next_move, &global_state.variant);
                     //if winner_card is alone_player add points
                     let winner_player = calculate_winner(winner_card.0, global_state);
-                    let winner_points = if winner_player == global_state.alone_player {
-                        winner_card.1
-                    } else {
+                    let winner_points = if winner_player != global_state.alone_player {
                         0
+                    } else if global_state.count_tricks {
+                        1
+                    } else {
+                        winner_card.1
                     };
                     move_sorter.add(
                         LState {
@@ -131,6 +172,12 @@ pub struct MoveSorter {
     entries: ArrayVec<(LState, u8, u8), 10>,
 }
 
+impl Default for MoveSorter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MoveSorter {
     pub fn new() -> MoveSorter {
         MoveSorter {
@@ -138,6 +185,8 @@ impl MoveSorter {
         }
     }
 
+    /// # Safety
+    /// At most 10 moves can be added, as no capacity check is done
     pub unsafe fn add(&mut self, move_: LState, score: u8, real: u8) {
         self.entries.push_unchecked((move_, score, real))
     }
@@ -5,5 +5,9 @@ pub mod ab;
 pub mod ab_tt;
 pub mod local_state;
 pub mod ab_tt_optimized;
-mod trans_table;
-pub(crate) mod ab_tt_bitstates;
\ No newline at end of file
+pub mod ramsch;
+pub mod threshold;
+pub mod limits;
+pub mod stats;
+pub mod trans_table;
+pub mod ab_tt_bitstates;
\ No newline at end of file
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use arrayvec::ArrayVec;
+use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better, EMPTY_CARD};
+use crate::solver::{calculate_current_suit_mask, calculate_next_moves, calculate_winner, GlobalState, Player};
+
+///
+/// In Ramsch there is no declarer, every player plays for themselves and tries to take as few
+/// points as possible. The skat goes to the winner of the last trick.
+/// A player taking every trick (Durchmarsch) wins instead, which is also the worst result for the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RamschState {
+    pub remaining_cards: BitCards,
+    pub current_player: Player,
+    pub current_played_cards: (BitCard, BitCard),
+    pub current_suit: Option<BitCards>,
+    //bit i is set if player i already took a trick
+    trick_takers: u8,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RamschResult {
+    pub points: [u8; 3],
+    pub tricks: [u8; 3],
+}
+
+impl RamschResult {
+    pub fn get_points(&self, player: Player) -> u8 {
+        self.points[player as usize]
+    }
+
+    ///A player who did not take a single trick
+    pub fn is_jungfrau(&self, player: Player) -> bool {
+        self.tricks[player as usize] == 0
+    }
+
+    ///The player who took every trick
+    pub fn durchmarsch(&self) -> Option<Player> {
+        let all_tricks: u8 = self.tricks.iter().sum();
+        if all_tricks == 0 {
+            return None;
+        }
+        self.tricks.iter().position(|tricks| *tricks == all_tricks).map(|player| Player::from(player as u8))
+    }
+
+    ///The players with the most points, nobody loses against a Durchmarsch
+    pub fn losers(&self) -> Vec<Player> {
+        if self.durchmarsch().is_some() {
+            return Vec::new();
+        }
+        let most = *self.points.iter().max().unwrap();
+        [Player::One, Player::Two, Player::Three].into_iter()
+            .filter(|player| self.get_points(*player) == most)
+            .collect()
+    }
+
+    fn add_trick(&mut self, winner: Player, points: u8) {
+        self.points[winner as usize] += points;
+        self.tricks[winner as usize] += 1;
+    }
+
+    fn get_trick_takers(&self) -> u8 {
+        let mut takers = 0;
+        for (player, tricks) in self.tricks.iter().enumerate() {
+            if *tricks != 0 {
+                takers |= 1 << player;
+            }
+        }
+        takers
+    }
+}
+
+impl RamschState {
+    pub fn new(remaining_cards: BitCards, current_player: Player) -> RamschState {
+        RamschState {
+            remaining_cards,
+            current_player,
+            current_played_cards: (EMPTY_CARD, EMPTY_CARD),
+            current_suit: None,
+            trick_takers: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn is_terminal(&self) -> bool {
+        self.remaining_cards.0 == 0
+    }
+
+    #[inline(always)]
+    fn is_full_node(&self) -> bool {
+        self.current_suit.is_none()
+    }
+
+    #[inline(always)]
+    fn get_hash(&self) -> (u32, Player, u8) {
+        (self.remaining_cards.0, self.current_player, self.trick_takers)
+    }
+
+    #[inline(always)]
+    fn get_available(&self, global_state: &GlobalState) -> BitCards {
+        match self.current_player {
+            Player::One => global_state.player_cards.0 & self.remaining_cards,
+            Player::Two => global_state.player_cards.1 & self.remaining_cards,
+            Player::Three => global_state.player_cards.2 & self.remaining_cards,
+        }
+    }
+
+    ///Returns the next states together with the winner of the trick and its points, if the move finished a trick
+    fn get_next_states(&self, global_state: &GlobalState) -> ArrayVec<(RamschState, Option<(Player, u8)>), 10> {
+        let mut next_states = ArrayVec::new();
+        let available = self.get_available(global_state);
+        let possible_moves = calculate_next_moves(available, self.current_suit);
+        let next_player = self.current_player.get_next_player();
+        for next_move in possible_moves {
+            let remaining_cards = BitCards(self.remaining_cards.0 & (!next_move.0));
+            match self.current_played_cards {
+                (BitCard(0), BitCard(0)) => {
+                    next_states.push((RamschState {
+                        remaining_cards,
+                        current_player: next_player,
+                        current_played_cards: (next_move, EMPTY_CARD),
+                        current_suit: Some(calculate_current_suit_mask(next_move, &global_state.variant)),
+                        trick_takers: self.trick_takers,
+                    }, None));
+                }
+                (_, BitCard(0)) => {
+                    next_states.push((RamschState {
+                        remaining_cards,
+                        current_player: next_player,
+                        current_played_cards: (self.current_played_cards.0, next_move),
+                        current_suit: self.current_suit,
+                        trick_takers: self.trick_takers,
+                    }, None));
+                }
+                (_, _) => {
+                    let (winner_card, mut points) = calculate_who_won_better(self.current_played_cards.0, self.current_played_cards.1, next_move, &global_state.variant);
+                    let winner = calculate_winner(winner_card, global_state);
+                    //the skat belongs to the last trick
+                    if remaining_cards.0 == 0 {
+                        points += global_state.skat_points;
+                    }
+                    next_states.push((RamschState {
+                        remaining_cards,
+                        current_player: winner,
+                        current_played_cards: (EMPTY_CARD, EMPTY_CARD),
+                        current_suit: None,
+                        trick_takers: self.trick_takers | (1 << winner as u8),
+                    }, Some((winner, points))));
+                }
+            }
+        }
+        next_states
+    }
+}
+
+///
+/// Max-n search: every player picks the move which is best for themselves.
+/// Results of full nodes only depend on the remaining cards, the current player and
+/// who already took a trick, so they are stored and reused.
+pub struct RamschSolver {
+    pub global_state: GlobalState,
+    pub look_up_table: HashMap<(u32, Player, u8), RamschResult>,
+}
+
+impl RamschSolver {
+    pub fn new(global_state: GlobalState) -> RamschSolver {
+        Self {
+            global_state,
+            look_up_table: Default::default(),
+        }
+    }
+
+    ///Points and tricks of the whole game, earlier holds what every player took before the solved state
+    pub fn solve(&mut self, mut local_state: RamschState, earlier: RamschResult) -> RamschResult {
+        local_state.trick_takers = earlier.get_trick_takers();
+        let mut result = self.max_n(local_state);
+        for player in 0..3 {
+            result.points[player] += earlier.points[player];
+            result.tricks[player] += earlier.tricks[player];
+        }
+        result
+    }
+
+    ///Points and tricks every player takes from this state on
+    fn max_n(&mut self, local_state: RamschState) -> RamschResult {
+        if local_state.is_terminal() {
+            return RamschResult::default();
+        }
+        if local_state.is_full_node() {
+            if let Some(result) = self.look_up_table.get(&local_state.get_hash()) {
+                return *result;
+            }
+        }
+        let mut best: Option<(i16, RamschResult)> = None;
+        for (next_state, trick) in local_state.get_next_states(&self.global_state) {
+            let mut result = self.max_n(next_state);
+            if let Some((winner, points)) = trick {
+                result.add_trick(winner, points);
+            }
+            let utility = get_utility(local_state.trick_takers, &result, local_state.current_player);
+            if best.is_none_or(|(best_utility, _)| utility > best_utility) {
+                best = Some((utility, result));
+            }
+        }
+        let result = best.unwrap().1;
+        if local_state.is_full_node() {
+            self.look_up_table.insert(local_state.get_hash(), result);
+        }
+        result
+    }
+}
+
+///Value of the remaining game for a player, only the points still to come matter
+/// as the points already taken can not be changed anymore
+#[inline(always)]
+fn get_utility(trick_takers: u8, result: &RamschResult, player: Player) -> i16 {
+    let takers = trick_takers | result.get_trick_takers();
+    if takers.count_ones() == 1 {
+        return if takers == 1 << player as u8 {
+            1000
+        } else {
+            -1000
+        };
+    }
+    -(result.get_points(player) as i16)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dataset::{read_dataset, SolvedPosition};
+    use crate::solver::bitboard::{BitCard, BitCards, calculate_who_won_better, HEARTS_ASS, HEARTS_EIGHT, HEARTS_JACK, HEARTS_KING, HEARTS_NINE, HEARTS_SEVEN, HEARTS_TEN, KARO_EIGHT, KARO_SEVEN, KREUZ_JACK, PIQUS_JACK};
+    use crate::solver::{calculate_current_suit_mask, calculate_next_moves, GlobalState, Player, Variant};
+    use crate::solver::synchronus::ramsch::{RamschResult, RamschSolver, RamschState};
+
+    #[test]
+    fn ramsch_durchmarsch() {
+        let player_one = KREUZ_JACK | PIQUS_JACK;
+        let player_two = KARO_SEVEN | KARO_EIGHT;
+        let player_three = HEARTS_SEVEN | HEARTS_EIGHT;
+        let skat = HEARTS_TEN | HEARTS_KING;
+        let global_state = GlobalState::new((player_one, player_two, player_three), skat, Player::One, Variant::Ramsch);
+        let mut solver = RamschSolver::new(global_state);
+        let result = solver.solve(RamschState::new(player_one | player_two | player_three, Player::One), RamschResult::default());
+        assert_eq!(result.points, [18, 0, 0]);
+        assert_eq!(result.tricks, [2, 0, 0]);
+        assert_eq!(result.durchmarsch(), Some(Player::One));
+        assert!(result.is_jungfrau(Player::Two));
+        assert!(result.is_jungfrau(Player::Three));
+        assert!(result.losers().is_empty());
+    }
+
+    #[test]
+    fn ramsch_earlier_tricks() {
+        //player one takes the remaining tricks, but player two already took one before
+        let player_one = KREUZ_JACK | PIQUS_JACK;
+        let player_two = KARO_SEVEN | KARO_EIGHT;
+        let player_three = HEARTS_SEVEN | HEARTS_EIGHT;
+        let global_state = GlobalState::new((player_one, player_two, player_three), BitCards(0), Player::One, Variant::Ramsch);
+        let mut solver = RamschSolver::new(global_state);
+        let earlier = RamschResult {
+            points: [0, 10, 0],
+            tricks: [0, 1, 0],
+        };
+        let result = solver.solve(RamschState::new(player_one | player_two | player_three, Player::One), earlier);
+        assert_eq!(result.points, [4, 10, 0]);
+        assert_eq!(result.tricks, [2, 1, 0]);
+        assert_eq!(result.durchmarsch(), None);
+        assert!(!result.is_jungfrau(Player::Two));
+        assert!(result.is_jungfrau(Player::Three));
+        assert_eq!(result.losers(), vec![Player::Two]);
+    }
+
+    #[test]
+    fn ramsch_skat_to_last_trick() {
+        //player two has to take a trick with the ace to stop a Durchmarsch of player three
+        let player_one = HEARTS_SEVEN | KARO_SEVEN;
+        let player_two = HEARTS_ASS | HEARTS_EIGHT;
+        let player_three = HEARTS_NINE | KARO_EIGHT;
+        let skat = HEARTS_TEN | HEARTS_JACK;
+        let global_state = GlobalState::new((player_one, player_two, player_three), skat, Player::One, Variant::Ramsch);
+        let mut solver = RamschSolver::new(global_state);
+        let result = solver.solve(RamschState::new(player_one | player_two | player_three, Player::One), RamschResult::default());
+        assert_eq!(result.durchmarsch(), None);
+        assert_eq!(result.points.iter().sum::<u8>(), 23);
+        assert_eq!(result.tricks.iter().sum::<u8>(), 2);
+        assert!(result.is_jungfrau(Player::One));
+        assert_eq!(result.losers(), vec![Player::Two]);
+    }
+
+    fn run_test(position: &SolvedPosition) {
+        let (p1, p2, p3) = position.player_cards;
+        let global_state = GlobalState::new(position.player_cards, position.skat, Player::One, Variant::Ramsch);
+        let all_points = (p1 | p2 | p3 | position.skat).get_cards_points();
+        let amount_cards = p1.0.count_ones() as u8;
+        let mut solver = RamschSolver::new(global_state);
+        let result = solver.solve(RamschState::new(position.get_remaining_cards(), position.current_player), RamschResult::default());
+        assert_eq!(result.points.iter().sum::<u8>(), all_points);
+        assert_eq!(result.tricks.iter().sum::<u8>(), amount_cards);
+        if let Some(player) = result.durchmarsch() {
+            assert_eq!(result.get_points(player), all_points);
+        }
+    }
+
+    ///Value of the whole game for a player, the points taken before a move are the same for every move
+    fn get_final_utility(result: &RamschResult, player: Player) -> i16 {
+        match result.durchmarsch() {
+            Some(winner) if winner == player => 1000,
+            Some(_) => -1000,
+            None => -(result.get_points(player) as i16),
+        }
+    }
+
+    ///Plain max-n search over the whole game without a table, ties keep the first move like the solver
+    fn exhaustive_max_n(hands: [BitCards; 3], skat: BitCards, player: Player, trick: &mut Vec<(Player, BitCard)>, result: RamschResult) -> RamschResult {
+        if hands.iter().all(|hand| hand.0 == 0) {
+            return result;
+        }
+        let suit = trick.first().map(|(_, card)| calculate_current_suit_mask(*card, &Variant::Ramsch));
+        let mut best: Option<(i16, RamschResult)> = None;
+        for card in calculate_next_moves(hands[player as usize], suit) {
+            let mut hands = hands;
+            hands[player as usize] = BitCards(hands[player as usize].0 & !card.0);
+            trick.push((player, card));
+            let final_result = if trick.len() == 3 {
+                let (winner_card, mut points) = calculate_who_won_better(trick[0].1, trick[1].1, trick[2].1, &Variant::Ramsch);
+                let winner = trick.iter().find(|(_, card)| *card == winner_card).unwrap().0;
+                if hands.iter().all(|hand| hand.0 == 0) {
+                    points += skat.get_cards_points();
+                }
+                let mut result = result;
+                result.points[winner as usize] += points;
+                result.tricks[winner as usize] += 1;
+                let finished = std::mem::take(trick);
+                let final_result = exhaustive_max_n(hands, skat, winner, trick, result);
+                *trick = finished;
+                final_result
+            } else {
+                exhaustive_max_n(hands, skat, player.get_next_player(), trick, result)
+            };
+            trick.pop();
+            let utility = get_final_utility(&final_result, player);
+            if best.is_none_or(|(best_utility, _)| utility > best_utility) {
+                best = Some((utility, final_result));
+            }
+        }
+        best.unwrap().1
+    }
+
+    #[test]
+    fn ramsch_exhaustive() {
+        for (number, position) in read_dataset("data/four_cards.txt").unwrap().into_iter().take(100).enumerate() {
+            let (p1, p2, p3) = position.player_cards;
+            //every second deal has two of the missing cards in the skat
+            let skat = if number % 2 == 0 { BitCards(!(p1 | p2 | p3).0).take(2).fold(BitCards(0), |cards, card| cards | card) } else { BitCards(0) };
+            let global_state = GlobalState::new(position.player_cards, skat, Player::One, Variant::Ramsch);
+            let mut solver = RamschSolver::new(global_state);
+            let result = solver.solve(RamschState::new(p1 | p2 | p3, position.current_player), RamschResult::default());
+            let expected = exhaustive_max_n([p1, p2, p3], skat, position.current_player, &mut Vec::new(), RamschResult::default());
+            assert_eq!(result, expected, "{position:?}");
+        }
+    }
+
+    #[test]
+    fn ramsch_four_cards() {
+        for position in read_dataset("data/four_cards.txt").unwrap() {
+            run_test(&position);
+        }
+    }
+
+    #[test]
+    fn ramsch_six_cards() {
+        for position in read_dataset("data/six_cards.txt").unwrap().into_iter().take(100) {
+            run_test(&position);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::ops::AddAssign;
+use crate::solver::synchronus::ab_tt::Bounds;
+
+///Counter for every kind of stored bound
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BoundsCount {
+    pub valid: u64,
+    pub lower_bound: u64,
+    pub upper_bound: u64,
+}
+
+impl BoundsCount {
+    pub fn total(&self) -> u64 {
+        self.valid + self.lower_bound + self.upper_bound
+    }
+
+    #[inline(always)]
+    fn add(&mut self, bound: Bounds) {
+        match bound {
+            Bounds::Valid => self.valid += 1,
+            Bounds::LowerBound => self.lower_bound += 1,
+            Bounds::UpperBound => self.upper_bound += 1,
+        }
+    }
+}
+
+impl AddAssign for BoundsCount {
+    fn add_assign(&mut self, rhs: Self) {
+        self.valid += rhs.valid;
+        self.lower_bound += rhs.lower_bound;
+        self.upper_bound += rhs.upper_bound;
+    }
+}
+
+///
+/// Counters of one solve. They are only collected with the `stats` feature,
+/// without it every counter stays zero and the search does no extra work.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub tt_probes: u64,
+    ///found entries by their kind of bound
+    pub tt_hits: BoundsCount,
+    ///found entries which ended the search of the node
+    pub tt_cutoffs: BoundsCount,
+    ///alpha beta cutoffs of the search
+    pub cutoffs: u64,
+    ///cutoffs caused by the first move, a measure of the move ordering
+    pub first_move_cutoffs: u64,
+    pub mtd_iterations: u64,
+    pub peak_table_size: usize,
+}
+
+impl SearchStats {
+    #[inline(always)]
+    pub(crate) fn add_node(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.nodes += 1;
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn add_probe(&mut self, _hit: Option<Bounds>) {
+        #[cfg(feature = "stats")]
+        {
+            self.tt_probes += 1;
+            if let Some(bound) = _hit {
+                self.tt_hits.add(bound);
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn add_tt_cutoff(&mut self, _bound: Bounds) {
+        #[cfg(feature = "stats")]
+        {
+            self.tt_cutoffs.add(_bound);
+        }
+    }
+
+    ///index is the position of the move which caused the cutoff
+    #[inline(always)]
+    pub(crate) fn add_cutoff(&mut self, _index: usize) {
+        #[cfg(feature = "stats")]
+        {
+            self.cutoffs += 1;
+            if _index == 0 {
+                self.first_move_cutoffs += 1;
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn add_mtd_iteration(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.mtd_iterations += 1;
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn update_table_size(&mut self, _size: impl FnOnce() -> usize) {
+        #[cfg(feature = "stats")]
+        {
+            self.peak_table_size = self.peak_table_size.max(_size());
+        }
+    }
+
+    ///Share of the cutoffs caused by the first move
+    pub fn get_first_move_rate(&self) -> f64 {
+        if self.cutoffs == 0 {
+            return 0.0;
+        }
+        self.first_move_cutoffs as f64 / self.cutoffs as f64
+    }
+}
+
+///Counters of several threads, the peak table size is shared so the largest one is kept
+impl AddAssign for SearchStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.nodes += rhs.nodes;
+        self.tt_probes += rhs.tt_probes;
+        self.tt_hits += rhs.tt_hits;
+        self.tt_cutoffs += rhs.tt_cutoffs;
+        self.cutoffs += rhs.cutoffs;
+        self.first_move_cutoffs += rhs.first_move_cutoffs;
+        self.mtd_iterations += rhs.mtd_iterations;
+        self.peak_table_size = self.peak_table_size.max(rhs.peak_table_size);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::solver::bitboard::BitCard;
+
+///
+/// Goal of the declarer for a threshold query.
+/// Points include the skat, e.g. 61 to win, 90 for Schneider.
+/// Tricks are needed for Schwarz, as a trick can be worth zero points.
+/// In null games both count the tricks of the declarer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Points(u8),
+    Tricks(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThresholdResult {
+    ///declarer reaches the target against every defence, in null games the defenders force the declarer
+    ///to take at least the target in tricks, so the declarer wins the null game if one trick is not reached
+    pub reached: bool,
+    ///card of the player to move which secures the result, none if the player to move can not change it
+    pub best_move: Option<BitCard>,
+}
+
+impl Target {
+    ///the target in the score of the search, skat points are added by the solvers
+    pub(crate) fn get_goal(&self, skat_points: u8) -> i8 {
+        match self {
+            Target::Points(points) => (*points).min(121) as i8 - skat_points as i8,
+            Target::Tricks(tricks) => (*tricks).min(11) as i8,
+        }
+    }
+
+    pub(crate) fn counts_tricks(&self) -> bool {
+        matches!(self, Target::Tricks(_))
+    }
+}
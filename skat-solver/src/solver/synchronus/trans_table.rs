@@ -1,45 +1,279 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use crate::solver::Player;
 use crate::solver::synchronus::ab_tt::Bounds;
 
-const SIZE: usize = (1 << 23) + 9;
+///Keys of the solvers, packed into at most 34 bits
+pub trait TableKey: Copy + Eq + Hash {
+    fn pack(&self) -> u64;
+}
+
+impl TableKey for u32 {
+    #[inline(always)]
+    fn pack(&self) -> u64 {
+        *self as u64
+    }
+}
+
+impl TableKey for (u32, Player) {
+    #[inline(always)]
+    fn pack(&self) -> u64 {
+        self.0 as u64 | (self.1 as u64) << 32
+    }
+}
+
+///
+/// Storage of the alpha beta solvers for already searched full nodes.
+/// depth is the amount of cards left in the hands and decides which entries are kept
+/// if the table is full.
+pub trait TranspositionTable<K: TableKey> {
+    fn get(&mut self, key: K) -> Option<(i8, Bounds)>;
+    fn put(&mut self, key: K, score: i8, bound: Bounds, depth: u8);
+    fn clear(&mut self);
+    ///Amount of stored positions
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+///Growing table, nothing is ever replaced
+impl<K: TableKey, S: BuildHasher> TranspositionTable<K> for HashMap<K, (i8, Bounds), S> {
+    #[inline(always)]
+    fn get(&mut self, key: K) -> Option<(i8, Bounds)> {
+        HashMap::get(self, &key).copied()
+    }
+
+    #[inline(always)]
+    fn put(&mut self, key: K, score: i8, bound: Bounds, _depth: u8) {
+        self.insert(key, (score, bound));
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TableStats {
+    pub probes: u64,
+    pub hits: u64,
+    ///probes where the bucket was filled by other positions
+    pub collisions: u64,
+    pub stores: u64,
+    ///entries of other positions which were overwritten
+    pub replacements: u64,
+}
+
+const DEFAULT_MEMORY: usize = 64 << 20;
+const BUCKET_SIZE: usize = 2;
+
+const KEY_MASK: u64 = (1 << 34) - 1;
+const SCORE_SHIFT: u32 = 34;
+const BOUND_SHIFT: u32 = 42;
+const DEPTH_SHIFT: u32 = 44;
+const OCCUPIED: u64 = 1 << 63;
+
+///key 0-33, score 34-41, bound 42-43, depth 44-48, occupied 63
+#[inline(always)]
+fn pack_entry(key: u64, score: i8, bound: Bounds, depth: u8) -> u64 {
+    let bound = match bound {
+        Bounds::Valid => 0,
+        Bounds::LowerBound => 1,
+        Bounds::UpperBound => 2,
+    };
+    OCCUPIED | key | (score as u8 as u64) << SCORE_SHIFT | bound << BOUND_SHIFT | ((depth & 31) as u64) << DEPTH_SHIFT
+}
+
+#[inline(always)]
+fn unpack_entry(entry: u64) -> (i8, Bounds) {
+    let score = (entry >> SCORE_SHIFT) as u8 as i8;
+    let bound = match (entry >> BOUND_SHIFT) & 3 {
+        0 => Bounds::Valid,
+        1 => Bounds::LowerBound,
+        _ => Bounds::UpperBound,
+    };
+    (score, bound)
+}
+
+#[inline(always)]
+fn get_depth(entry: u64) -> u8 {
+    ((entry >> DEPTH_SHIFT) & 31) as u8
+}
+
+///
+/// Table with a fixed amount of memory, every entry is packed into 8 bytes.
+/// A bucket has two slots, the first keeps the deepest entry and the second
+/// always takes the newest one.
+pub struct FixedTable {
+    entries: Vec<u64>,
+    shift: u32,
+    used: usize,
+    stats: TableStats,
+}
 
-pub struct TranspositionTable {
-    keys: Vec<u32>,
-    values: Vec<i8>,
+impl Default for FixedTable {
+    fn default() -> Self {
+        FixedTable::with_memory(DEFAULT_MEMORY)
+    }
 }
-impl TranspositionTable {
-    pub fn new() -> TranspositionTable {
-        TranspositionTable {
-            keys: vec![0; SIZE],
-            values: vec![0; SIZE],
+
+impl FixedTable {
+    ///Uses at most the given amount of bytes, rounded down to a power of two
+    pub fn with_memory(bytes: usize) -> FixedTable {
+        let buckets = (bytes / (8 * BUCKET_SIZE)).max(1);
+        let buckets = 1usize << buckets.ilog2();
+        FixedTable {
+            entries: vec![0; buckets * BUCKET_SIZE],
+            shift: 64 - buckets.ilog2(),
+            used: 0,
+            stats: TableStats::default(),
         }
     }
 
-    fn index(&self, key: u32) -> usize {
-        (key % (SIZE as u32)) as usize
+    pub fn get_stats(&self) -> TableStats {
+        self.stats
+    }
+
+    pub fn get_memory(&self) -> usize {
+        self.entries.len() * 8
+    }
+
+    ///Amount of stored positions
+    pub fn len(&self) -> usize {
+        self.used
     }
 
-    /**
-     * Store a value for a given key
-     * @param key: must be less than key_size bits.
-     * @param value: must be less than value_size bits. null (0) value is used to encode missing data
-     */
-    pub fn put(&mut self, key: u32, value: i8, bounds: Bounds) {
-        let pos = self.index(key);
-        self.keys[pos] = key;
-        self.values[pos] = value
+    pub fn is_empty(&self) -> bool {
+        self.used == 0
+    }
+
+    #[inline(always)]
+    fn get_bucket(&self, key: u64) -> usize {
+        if self.shift == 64 {
+            return 0;
+        }
+        //fibonacci hashing, the low bits of the keys are single cards
+        (key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> self.shift) as usize * BUCKET_SIZE
+    }
+}
+
+impl<K: TableKey> TranspositionTable<K> for FixedTable {
+    #[inline(always)]
+    fn get(&mut self, key: K) -> Option<(i8, Bounds)> {
+        let key = key.pack();
+        let bucket = self.get_bucket(key);
+        self.stats.probes += 1;
+        let mut occupied = false;
+        for entry in &self.entries[bucket..bucket + BUCKET_SIZE] {
+            if entry & OCCUPIED == 0 {
+                continue;
+            }
+            if entry & KEY_MASK == key {
+                self.stats.hits += 1;
+                return Some(unpack_entry(*entry));
+            }
+            occupied = true;
+        }
+        if occupied {
+            self.stats.collisions += 1;
+        }
+        None
     }
 
-    /**
-     * Get the value of a key
-     * @param key: must be less than key_size bits.
-     * @return value_size bits value associated with the key if present, 0 otherwise.
-     */
-    pub fn get(&self, key: u32) -> Option<i8> {
-        let pos = self.index(key);
-        if self.keys[pos] == key {
-            Some(self.values[pos])
+    #[inline(always)]
+    fn put(&mut self, key: K, score: i8, bound: Bounds, depth: u8) {
+        let key = key.pack();
+        let bucket = self.get_bucket(key);
+        let entry = pack_entry(key, score, bound, depth);
+        self.stats.stores += 1;
+        let (first, second) = (self.entries[bucket], self.entries[bucket + 1]);
+        if first & OCCUPIED != 0 && first & KEY_MASK == key {
+            self.entries[bucket] = entry;
+            return;
+        }
+        if second & OCCUPIED != 0 && second & KEY_MASK == key {
+            //a deeper search of the same position can move up
+            if depth >= get_depth(first) || first & OCCUPIED == 0 {
+                self.entries[bucket] = entry;
+                self.entries[bucket + 1] = first;
+            } else {
+                self.entries[bucket + 1] = entry;
+            }
+            return;
+        }
+        if second & OCCUPIED != 0 {
+            self.stats.replacements += 1;
+        } else {
+            //the new entry or the moved first one fills the empty slot
+            self.used += 1;
+        }
+        if first & OCCUPIED == 0 || depth >= get_depth(first) {
+            self.entries[bucket] = entry;
+            self.entries[bucket + 1] = first;
         } else {
-            None
+            self.entries[bucket + 1] = entry;
         }
     }
-}
\ No newline at end of file
+
+    fn clear(&mut self) {
+        self.entries.fill(0);
+        self.used = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Player;
+    use crate::solver::synchronus::ab_tt::Bounds;
+    use crate::solver::synchronus::trans_table::{FixedTable, TranspositionTable};
+
+    #[test]
+    fn fixed_table_packing() {
+        let mut table = FixedTable::with_memory(1 << 10);
+        assert_eq!(table.get_memory(), 1 << 10);
+        table.put(u32::MAX, -120, Bounds::UpperBound, 30);
+        table.put(7, 120, Bounds::LowerBound, 1);
+        assert_eq!(TranspositionTable::<u32>::get(&mut table, u32::MAX), Some((-120, Bounds::UpperBound)));
+        assert_eq!(TranspositionTable::<u32>::get(&mut table, 7), Some((120, Bounds::LowerBound)));
+        assert_eq!(TranspositionTable::<u32>::get(&mut table, 8), None);
+        table.put((7, Player::Three), 3, Bounds::Valid, 1);
+        assert_eq!(table.get((7, Player::Three)), Some((3, Bounds::Valid)));
+        assert_eq!(table.get((7, Player::Two)), None);
+        let stats = table.get_stats();
+        assert_eq!(stats.probes, 5);
+        assert_eq!(stats.hits, 3);
+        TranspositionTable::<u32>::clear(&mut table);
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn fixed_table_replacement() {
+        //a single bucket
+        let mut table = FixedTable::with_memory(16);
+        table.put(1u32, 1, Bounds::Valid, 10);
+        table.put(2u32, 2, Bounds::Valid, 2);
+        //replaces the newest entry, the deep one stays
+        table.put(3u32, 3, Bounds::Valid, 3);
+        assert_eq!(table.get(1u32), Some((1, Bounds::Valid)));
+        assert_eq!(table.get(2u32), None);
+        assert_eq!(table.get(3u32), Some((3, Bounds::Valid)));
+        //a deeper entry takes the first slot
+        table.put(4u32, 4, Bounds::Valid, 12);
+        assert_eq!(table.get(4u32), Some((4, Bounds::Valid)));
+        assert_eq!(table.get(1u32), Some((1, Bounds::Valid)));
+        assert_eq!(table.get(3u32), None);
+        let stats = table.get_stats();
+        assert_eq!(stats.replacements, 2);
+        assert_eq!(stats.collisions, 2);
+        assert_eq!(table.len(), 2);
+    }
+}